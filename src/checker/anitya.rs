@@ -1,12 +1,13 @@
 use std::collections::HashMap;
 
-use super::UpdateChecker;
+use super::{read_json, send, UpdateChecker};
 use crate::must_have;
 use anyhow::{anyhow, Result};
 use reqwest::blocking::Client;
 use serde::Deserialize;
 
 const API_ENDPOINT: &str = "https://release-monitoring.org/api/project/";
+const API_V2_ENDPOINT: &str = "https://release-monitoring.org/api/v2/versions/";
 
 #[derive(Deserialize)]
 struct AnityaData {
@@ -15,27 +16,23 @@ struct AnityaData {
     versions: Vec<String>,
 }
 
+#[derive(Deserialize)]
+struct AnityaV2Data {
+    latest_version: Option<String>,
+    stable_versions: Vec<String>,
+}
+
 pub(crate) struct AnityaChecker {
     id: usize,
     stable_only: bool,
+    use_v2: bool,
 }
 
-impl UpdateChecker for AnityaChecker {
-    fn new(config: &HashMap<String, String>) -> Result<Self> {
-        let id = must_have!(config, "id", "Anitya project ID")?.parse::<usize>()?;
-        let stable_only = if let Some(stable_only) = config.get("stable_only") {
-            stable_only == "true"
-        } else {
-            true
-        };
-
-        Ok(AnityaChecker { id, stable_only })
-    }
-
-    fn check(&self, client: &Client) -> Result<String> {
-        let resp = client.get(format!("{}{}/", API_ENDPOINT, self.id)).send()?;
+impl AnityaChecker {
+    fn check_v1(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(format!("{}{}/", API_ENDPOINT, self.id)))?;
         resp.error_for_status_ref()?;
-        let payload: AnityaData = resp.json()?;
+        let payload: AnityaData = read_json(resp)?;
         if payload.id != self.id {
             return Err(anyhow!(
                 "The unthinkable happened: requested ID and received ID mismatch."
@@ -52,6 +49,62 @@ impl UpdateChecker for AnityaChecker {
 
         Ok(versions[0].clone())
     }
+
+    fn check_v2(&self, client: &Client) -> Result<String> {
+        let resp = send(
+            client,
+            client
+                .get(API_V2_ENDPOINT)
+                .query(&[("project_id", self.id)]),
+        )?;
+        resp.error_for_status_ref()?;
+        let payload: AnityaV2Data = read_json(resp)?;
+        let version = if self.stable_only {
+            payload.stable_versions.into_iter().next()
+        } else {
+            payload.latest_version
+        };
+
+        version.ok_or_else(|| anyhow!("Anitya didn't return any stable versions!"))
+    }
+}
+
+impl UpdateChecker for AnityaChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self> {
+        let id = must_have!(config, "id", "Anitya project ID")?.parse::<usize>()?;
+        let stable_only = if let Some(stable_only) = config.get("stable_only") {
+            stable_only == "true"
+        } else {
+            true
+        };
+        let use_v2 = match config.get("api").map(|s| s.as_str()) {
+            None | Some("v1") => false,
+            Some("v2") => true,
+            Some(api) => return Err(anyhow!("Unknown Anitya API version: {}", api)),
+        };
+
+        Ok(AnityaChecker {
+            id,
+            stable_only,
+            use_v2,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        if self.use_v2 {
+            format!("{}?project_id={}", API_V2_ENDPOINT, self.id)
+        } else {
+            format!("{}{}/", API_ENDPOINT, self.id)
+        }
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        if self.use_v2 {
+            self.check_v2(client)
+        } else {
+            self.check_v1(client)
+        }
+    }
 }
 
 #[test]
@@ -62,3 +115,13 @@ fn test_check_anitya() {
     let checker = AnityaChecker::new(&options).unwrap();
     checker.check(&client).unwrap();
 }
+
+#[test]
+fn test_check_anitya_v2() {
+    let mut options = HashMap::new();
+    options.insert("id".to_string(), "1832".to_string()); // lmms
+    options.insert("api".to_string(), "v2".to_string());
+    let client = Client::new();
+    let checker = AnityaChecker::new(&options).unwrap();
+    checker.check(&client).unwrap();
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use super::{read_json, send, UpdateChecker};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://archlinux.org/packages/search/json/";
+
+#[derive(Deserialize)]
+struct ArchPackage {
+    pkgver: String,
+}
+
+#[derive(Deserialize)]
+struct ArchData {
+    results: Vec<ArchPackage>,
+}
+
+pub(crate) struct ArchLinuxChecker {
+    pkg: String,
+    repo: Option<String>,
+}
+
+/// Converts the repository name to the capitalized form used by the API (e.g. `core` to `Core`,
+/// `core-testing` to `Core-Testing`)
+fn repo_name(repo: &str) -> String {
+    repo.split('-')
+        .map(|segment| {
+            let mut chars = segment.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+impl ArchLinuxChecker {
+    fn query(&self) -> Vec<(&str, String)> {
+        let mut query = vec![("name", self.pkg.clone())];
+        if let Some(repo) = &self.repo {
+            query.push(("repo", repo_name(repo)));
+        }
+
+        query
+    }
+}
+
+impl UpdateChecker for ArchLinuxChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        Ok(ArchLinuxChecker {
+            pkg: must_have!(config, "pkg", "Arch Linux package name")?.to_string(),
+            repo: config.get("repo").cloned(),
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        let query = self
+            .query()
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        format!("{}?{}", API_ENDPOINT, query)
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(API_ENDPOINT).query(&self.query()))?;
+        resp.error_for_status_ref()?;
+        let payload: ArchData = read_json(resp)?;
+        let package = payload.results.first().ok_or_else(|| {
+            anyhow!(
+                "Package {} is not found in the Arch Linux repositories!",
+                self.pkg
+            )
+        })?;
+
+        Ok(package.pkgver.clone())
+    }
+}
+
+#[test]
+fn test_repo_name() {
+    assert_eq!(repo_name("core"), "Core");
+    assert_eq!(repo_name("Extra"), "Extra");
+    assert_eq!(repo_name("core-testing"), "Core-Testing");
+    assert_eq!(repo_name("extra-staging"), "Extra-Staging");
+}
+
+#[test]
+fn test_archlinux() {
+    let mut options = HashMap::new();
+    options.insert("pkg".to_string(), "pacman".to_string());
+    options.insert("repo".to_string(), "core".to_string());
+    let client = Client::new();
+    let checker = ArchLinuxChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
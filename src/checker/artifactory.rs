@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use super::{
+    collect_links, extract_versions, pattern_from_config, read_text, send, UpdateChecker,
+    VersionOrder,
+};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::AUTHORIZATION;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct ArtifactoryFile {
+    uri: String,
+}
+
+#[derive(Deserialize)]
+struct ArtifactoryListing {
+    files: Vec<ArtifactoryFile>,
+}
+
+pub(crate) struct ArtifactoryChecker {
+    url: String,
+    pattern: String,
+    order: VersionOrder,
+}
+
+/// Converts an Artifactory repository URL to the corresponding storage API URL
+fn storage_api_url(url: &str) -> Option<String> {
+    if url.contains("/api/storage/") {
+        return Some(url.to_string());
+    }
+    let (base, path) = url.split_once("/artifactory/")?;
+
+    Some(format!("{}/artifactory/api/storage/{}", base, path))
+}
+
+impl ArtifactoryChecker {
+    fn authorize(&self, builder: RequestBuilder) -> RequestBuilder {
+        if let Ok(token) = std::env::var("ARTIFACTORY_TOKEN") {
+            builder.header(AUTHORIZATION, format!("Bearer {}", token))
+        } else {
+            builder
+        }
+    }
+
+    fn list_json(&self, client: &Client) -> Result<Vec<String>> {
+        let url = storage_api_url(&self.url)
+            .ok_or_else(|| anyhow!("Not an Artifactory repository URL"))?;
+        let resp = send(
+            client,
+            self.authorize(client.get(format!("{}?list&deep=0", url.trim_end_matches('/')))),
+        )?;
+        resp.error_for_status_ref()?;
+        let payload: ArtifactoryListing = serde_json::from_str(&read_text(resp)?)?;
+
+        Ok(payload
+            .files
+            .into_iter()
+            .map(|x| x.uri.trim_start_matches('/').to_string())
+            .collect())
+    }
+
+    fn list_html(&self, client: &Client) -> Result<Vec<String>> {
+        let resp = send(client, self.authorize(client.get(&self.url)))?;
+        resp.error_for_status_ref()?;
+        let base = resp.url().clone();
+
+        collect_links(&read_text(resp)?, &base)
+    }
+}
+
+impl UpdateChecker for ArtifactoryChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        Ok(ArtifactoryChecker {
+            url: must_have!(config, "url", "Repository directory URL")?.to_string(),
+            pattern: pattern_from_config(config)?
+                .ok_or_else(|| anyhow!("Please specify Regex pattern for matching versions!"))?,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        self.url.clone()
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let files = match self.list_json(client) {
+            Ok(files) => files,
+            Err(e) => {
+                debug!("JSON listing unavailable ({}), falling back to HTML", e);
+                self.list_html(client)?
+            }
+        };
+        debug!("returned files: {:?}", files);
+        let mut versions = extract_versions(&self.pattern, &files)?;
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!("No version matches the pattern."));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.first().unwrap().to_string())
+    }
+}
+
+#[test]
+fn test_storage_api_url() {
+    assert_eq!(
+        storage_api_url("https://example.com/artifactory/generic-local/foo/").unwrap(),
+        "https://example.com/artifactory/api/storage/generic-local/foo/"
+    );
+    assert_eq!(
+        storage_api_url("https://example.com/artifactory/api/storage/generic-local/foo").unwrap(),
+        "https://example.com/artifactory/api/storage/generic-local/foo"
+    );
+    assert!(storage_api_url("https://nexus.example.com/repository/raw/foo/").is_none());
+}
+
+#[test]
+fn test_artifactory() {
+    let mut options = HashMap::new();
+    options.insert(
+        "url".to_string(),
+        "https://releases.jfrog.io/artifactory/jfrog-cli/v2-jf/".to_string(),
+    );
+    options.insert("pattern".to_string(), r"^(\d+\.\d+\.\d+)$".to_string());
+    let client = Client::new();
+    let checker = ArtifactoryChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use super::{read_json, send, UpdateChecker};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://aur.archlinux.org/rpc/v5/info/";
+
+#[derive(Deserialize)]
+struct AurPackage {
+    #[serde(rename = "Version")]
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct AurData {
+    results: Vec<AurPackage>,
+}
+
+pub(crate) struct AurChecker {
+    name: String,
+}
+
+/// Strips the epoch (`1:`) and the `-pkgrel` suffix from an Arch Linux package version
+fn strip_pkgrel(version: &str) -> &str {
+    let version = version.split_once(':').map_or(version, |(_, v)| v);
+
+    version.rsplit_once('-').map_or(version, |(v, _)| v)
+}
+
+impl UpdateChecker for AurChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        Ok(AurChecker {
+            name: must_have!(config, "name", "AUR package name")?.to_string(),
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!(
+            "{}{}",
+            API_ENDPOINT,
+            utf8_percent_encode(&self.name, NON_ALPHANUMERIC)
+        )
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(self.endpoint()))?;
+        resp.error_for_status_ref()?;
+        let payload: AurData = read_json(resp)?;
+        let package = payload
+            .results
+            .first()
+            .ok_or_else(|| anyhow!("Package {} is not found in the AUR!", self.name))?;
+
+        Ok(strip_pkgrel(&package.version).to_string())
+    }
+}
+
+#[test]
+fn test_strip_pkgrel() {
+    assert_eq!(strip_pkgrel("1.2.3-1"), "1.2.3");
+    assert_eq!(strip_pkgrel("2:1.2.3-4"), "1.2.3");
+    assert_eq!(strip_pkgrel("r123.abcdef-1"), "r123.abcdef");
+    assert_eq!(strip_pkgrel("1.2.3"), "1.2.3");
+}
+
+#[test]
+fn test_aur() {
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "yay".to_string());
+    let client = Client::new();
+    let checker = AurChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://api.bitbucket.org/2.0/repositories";
+/// Maximum number of pages followed, in case the listing never ends
+const MAX_PAGES: usize = 10;
+
+#[derive(Deserialize)]
+struct BitbucketTag {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct BitbucketPage {
+    values: Vec<BitbucketTag>,
+    next: Option<String>,
+}
+
+pub(crate) struct BitbucketChecker {
+    repo: String,
+    pattern: Option<String>,
+    order: VersionOrder,
+}
+
+impl BitbucketChecker {
+    fn select_version(&self, mut tags: Vec<String>) -> Result<String> {
+        debug!("returned tags: {:?}", tags);
+        if let Some(pattern) = &self.pattern {
+            tags = extract_versions(pattern, &tags)?;
+        }
+        debug!("after filter: {:?}", tags);
+        if tags.is_empty() {
+            return Err(anyhow!("Bitbucket didn't return any tags!"));
+        }
+        // the API can only order the tags by name, which puts `v1.9` before `v1.10`
+        self.order.sort(&mut tags)?;
+        debug!("after sort: {:?}", tags);
+
+        Ok(tags.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for BitbucketChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let repo = must_have!(config, "repo", "Repository slug")?.to_string();
+        if repo.split('/').count() != 2 || repo.split('/').any(|x| x.is_empty()) {
+            return Err(anyhow!(
+                "Invalid repository slug {}, expected workspace/repo-slug",
+                repo
+            ));
+        }
+        Ok(BitbucketChecker {
+            repo,
+            pattern: pattern_from_config(config)?,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/{}/refs/tags", API_ENDPOINT, self.repo)
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let mut tags = Vec::new();
+        let mut url = Some(format!("{}?pagelen=100&sort=-name", self.endpoint()));
+        for _ in 0..MAX_PAGES {
+            let Some(current) = url.take() else {
+                break;
+            };
+            let resp = send(client, client.get(&current))?;
+            resp.error_for_status_ref()?;
+            let page: BitbucketPage = read_json(resp)?;
+            tags.extend(page.values.into_iter().map(|x| x.name));
+            url = page.next;
+        }
+        if url.is_some() {
+            debug!("stopped after {} pages", MAX_PAGES);
+        }
+
+        self.select_version(tags)
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let page: BitbucketPage = serde_json::from_str(
+        r#"{
+            "pagelen": 100,
+            "values": [
+                {"name": "v1.9", "type": "tag", "target": {"hash": "aaaa"}},
+                {"name": "v1.10", "type": "tag", "target": {"hash": "bbbb"}},
+                {"name": "nightly", "type": "tag", "target": {"hash": "cccc"}}
+            ],
+            "page": 1,
+            "next": "https://api.bitbucket.org/2.0/repositories/foo/bar/refs/tags?pagelen=100&sort=-name&page=2"
+        }"#,
+    )
+    .unwrap();
+    assert!(page.next.unwrap().ends_with("page=2"));
+    let tags = page.values.into_iter().map(|x| x.name).collect::<Vec<_>>();
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "foo/bar".to_string());
+    let checker = BitbucketChecker::new(&options).unwrap();
+    assert_eq!(checker.select_version(tags.clone()).unwrap(), "v1.10");
+    options.insert("pattern".to_string(), r"^v([\d.]+)$".to_string());
+    let checker = BitbucketChecker::new(&options).unwrap();
+    assert_eq!(checker.select_version(tags).unwrap(), "1.10");
+    assert!(checker.select_version(vec!["nightly".to_string()]).is_err());
+    options.insert("repo".to_string(), "foo".to_string());
+    assert!(BitbucketChecker::new(&options).is_err());
+}
+
+#[test]
+fn test_bitbucket() {
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "multicoreware/x265_git".to_string());
+    options.insert("pattern".to_string(), r"^(\d+\.\d+(?:\.\d+)?)$".to_string());
+    let client = Client::new();
+    let checker = BitbucketChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_text, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+
+/// Matches headings like `## [1.2.3] - 2024-01-01`, `## 1.2.3` or `### v1.2.3 (2024-01-01)`
+const DEFAULT_PATTERN: &str = r"^#+\s*\[?v?(\d+(?:\.\d+)+[^\]\s]*)\]?";
+
+pub(crate) struct ChangelogChecker {
+    url: String,
+    pattern: String,
+    order: VersionOrder,
+}
+
+/// Extracts the versions from the lines of the changelog
+fn collect_versions(body: &str, pattern: &str) -> Result<Vec<String>> {
+    let lines = body.lines().map(|l| l.trim_end()).collect::<Vec<_>>();
+
+    extract_versions(pattern, &lines)
+}
+
+impl UpdateChecker for ChangelogChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        Ok(ChangelogChecker {
+            url: must_have!(config, "url", "Changelog URL")?.to_string(),
+            pattern: pattern_from_config(config)?.unwrap_or_else(|| DEFAULT_PATTERN.to_string()),
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        self.url.clone()
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(&self.url))?;
+        resp.error_for_status_ref()?;
+        let mut versions = collect_versions(&read_text(resp)?, &self.pattern)?;
+        debug!("matched versions: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!("No version matches the pattern."));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.first().unwrap().to_string())
+    }
+}
+
+#[test]
+fn test_collect_versions() {
+    let body = "# Changelog
+
+## [Unreleased]
+
+## [1.10.0] - 2024-03-01
+### Added
+- Support 2.0 configs
+
+## [1.9.2] - 2024-01-01
+
+## v1.9.1 (2023-12-01)
+
+## 1.9.0
+";
+    assert_eq!(
+        collect_versions(body, DEFAULT_PATTERN).unwrap(),
+        vec!["1.10.0", "1.9.2", "1.9.1", "1.9.0"]
+    );
+    let body = "Version 2.1\n===========\n\nVersion 2.0\n===========\n";
+    assert_eq!(
+        collect_versions(body, r"^Version (\d+\.\d+)$").unwrap(),
+        vec!["2.1", "2.0"]
+    );
+}
+
+#[test]
+fn test_changelog() {
+    let mut options = HashMap::new();
+    options.insert(
+        "url".to_string(),
+        "https://raw.githubusercontent.com/olivierlacan/keep-a-changelog/main/CHANGELOG.md"
+            .to_string(),
+    );
+    let client = Client::new();
+    let checker = ChangelogChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use super::{read_json, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://fastapi.metacpan.org/v1/release";
+
+#[derive(Deserialize)]
+struct CpanRelease {
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct CpanReleases {
+    releases: Vec<CpanRelease>,
+}
+
+/// Developer releases (e.g. `1.23_01`) are marked with an underscore
+fn is_developer_release(version: &str) -> bool {
+    version.contains('_')
+}
+
+pub(crate) struct CpanChecker {
+    dist: String,
+    stable_only: bool,
+    order: VersionOrder,
+}
+
+impl CpanChecker {
+    fn select_version(&self, releases: Vec<CpanRelease>) -> Result<String> {
+        let mut versions = releases
+            .into_iter()
+            .map(|r| r.version)
+            .filter(|v| !is_developer_release(v))
+            .collect::<Vec<_>>();
+        debug!("stable releases: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!("{} has no stable release on CPAN!", self.dist));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for CpanChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let dist = must_have!(config, "dist", "Distribution name")?.to_string();
+        let stable_only = config
+            .get("stable_only")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(CpanChecker {
+            dist,
+            stable_only,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/{}", API_ENDPOINT, self.dist)
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(self.endpoint()))?;
+        resp.error_for_status_ref()?;
+        let latest: CpanRelease = read_json(resp)?;
+        debug!("latest release: {}", latest.version);
+        if !self.stable_only || !is_developer_release(&latest.version) {
+            return Ok(latest.version);
+        }
+        // the latest release is a developer release, look for the newest stable one instead
+        let resp = send(
+            client,
+            client.get(format!("{}/versions/{}", API_ENDPOINT, self.dist)),
+        )?;
+        resp.error_for_status_ref()?;
+        let payload: CpanReleases = read_json(resp)?;
+
+        self.select_version(payload.releases)
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let payload: CpanReleases = serde_json::from_str(
+        r#"{
+            "total": 5,
+            "releases": [
+                {"version": "0.11_01", "maturity": "developer", "status": "cpan"},
+                {"version": "0.10", "maturity": "released", "status": "latest"},
+                {"version": "0.9", "maturity": "released", "status": "cpan"},
+                {"version": "0.09_02", "maturity": "developer", "status": "backpan"},
+                {"version": "0.08", "maturity": "released", "status": "backpan"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    let mut options = HashMap::new();
+    options.insert("dist".to_string(), "Foo-Bar".to_string());
+    options.insert("stable_only".to_string(), "true".to_string());
+    let checker = CpanChecker::new(&options).unwrap();
+    // versions are compared with `version_compare`, segment by segment, so `0.10` is newer
+    // than `0.9` even though Perl itself would compare them as decimals (0.100 < 0.900)
+    assert_eq!(checker.select_version(payload.releases).unwrap(), "0.10");
+    assert!(checker
+        .select_version(vec![CpanRelease {
+            version: "1.00_01".to_string()
+        }])
+        .is_err());
+    assert!(is_developer_release("1.23_01"));
+    assert!(!is_developer_release("v1.2.3"));
+}
+
+#[test]
+fn test_cpan() {
+    let mut options = HashMap::new();
+    options.insert("dist".to_string(), "Moose".to_string());
+    options.insert("stable_only".to_string(), "true".to_string());
+    let client = Client::new();
+    let checker = CpanChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use super::{
+    collect_links, extract_versions, pattern_from_config, read_text_bounded, send, UpdateChecker,
+    VersionOrder,
+};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+
+pub(crate) struct DirListingChecker {
+    url: String,
+    pattern: String,
+    order: VersionOrder,
+}
+
+impl UpdateChecker for DirListingChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        Ok(DirListingChecker {
+            url: must_have!(config, "url", "Directory listing URL")?.to_string(),
+            pattern: pattern_from_config(config)?
+                .ok_or_else(|| anyhow!("Please specify pattern!"))?,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        self.url.clone()
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(&self.url))?;
+        resp.error_for_status_ref()?;
+        // relative links are resolved against the final URL, after any redirection
+        let base = resp.url().clone();
+        let entries = collect_links(&read_text_bounded(resp)?, &base)?;
+        debug!("returned entries: {:?}", entries);
+        let mut versions = extract_versions(&self.pattern, &entries)?;
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!(
+                "No entry in the directory listing matches the pattern."
+            ));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+#[test]
+fn test_dirlisting() {
+    let mut options = HashMap::new();
+    options.insert(
+        "url".to_string(),
+        "https://ftp.gnu.org/gnu/hello/".to_string(),
+    );
+    options.insert(
+        "pattern".to_string(),
+        r"^hello-([\d.]+)\.tar\.gz$".to_string(),
+    );
+    let client = Client::new();
+    let checker = DirListingChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
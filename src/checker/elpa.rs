@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use super::{read_text, send, UpdateChecker};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use regex::{escape, Regex};
+use reqwest::blocking::Client;
+
+const DEFAULT_ARCHIVE: &str = "melpa";
+
+/// Base URLs of the well-known package archives
+const ARCHIVES: &[(&str, &str)] = &[
+    ("gnu", "https://elpa.gnu.org/packages/"),
+    ("gnu-devel", "https://elpa.gnu.org/devel/"),
+    ("nongnu", "https://elpa.nongnu.org/nongnu/"),
+    ("nongnu-devel", "https://elpa.nongnu.org/nongnu-devel/"),
+    ("melpa", "https://melpa.org/packages/"),
+    ("melpa-stable", "https://stable.melpa.org/packages/"),
+];
+
+/// Formats an Emacs version list (see `version-to-list`), e.g. `(1 0 -3 2)` becomes `1.0alpha2`
+fn format_version(parts: &[i64]) -> String {
+    let mut version = String::new();
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            -1 => version.push_str("rc"),
+            -2 => version.push_str("beta"),
+            -3 => version.push_str("alpha"),
+            -4 => version.push_str("snapshot"),
+            _ => {
+                if i > 0 && parts[i - 1] >= 0 {
+                    version.push('.');
+                }
+                version.push_str(&part.to_string());
+            }
+        }
+    }
+
+    version
+}
+
+/// Finds the version of the package in the `archive-contents` file
+fn find_version(contents: &str, name: &str) -> Option<String> {
+    let pattern = Regex::new(&format!(
+        r"\(\s*{}\s+\.\s+\[\s*\(([-\d\s]*)\)",
+        escape(name)
+    ))
+    .ok()?;
+    let captures = pattern.captures(contents)?;
+    let parts = captures[1]
+        .split_whitespace()
+        .map(|x| x.parse::<i64>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    if parts.is_empty() {
+        return None;
+    }
+
+    Some(format_version(&parts))
+}
+
+pub(crate) struct ElpaChecker {
+    name: String,
+    archive: String,
+    url: String,
+}
+
+impl UpdateChecker for ElpaChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let name = must_have!(config, "name", "Package name")?.to_string();
+        let archive = config
+            .get("archive")
+            .map(|s| s.as_str())
+            .unwrap_or(DEFAULT_ARCHIVE);
+        let url = if archive.starts_with("https://") || archive.starts_with("http://") {
+            archive.to_string()
+        } else {
+            ARCHIVES
+                .iter()
+                .find(|(k, _)| *k == archive)
+                .map(|(_, v)| v.to_string())
+                .ok_or_else(|| anyhow!("Unknown package archive: {}", archive))?
+        };
+
+        Ok(ElpaChecker {
+            name,
+            archive: archive.to_string(),
+            url,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/archive-contents", self.url.trim_end_matches('/'))
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(self.endpoint()))?;
+        resp.error_for_status_ref()?;
+        let contents = read_text(resp)?;
+
+        find_version(&contents, &self.name).ok_or_else(|| {
+            anyhow!(
+                "Package {} is not found in the {} archive!",
+                self.name,
+                self.archive
+            )
+        })
+    }
+}
+
+#[test]
+fn test_format_version() {
+    assert_eq!(format_version(&[2, 5, 1]), "2.5.1");
+    assert_eq!(format_version(&[20240101, 1200]), "20240101.1200");
+    assert_eq!(format_version(&[1, 0, -3, 2]), "1.0alpha2");
+    assert_eq!(format_version(&[3, 0, -1]), "3.0rc");
+}
+
+#[test]
+fn test_find_version() {
+    let contents = r#"(1
+ (ace-window .
+             [(0 10 0)
+              ((avy (0 5 0)))
+              "Quickly switch windows." tar
+              ((:url . "https://github.com/abo-abo/ace-window"))])
+ (magit . [(20240426 2118) ((emacs (25 1)) (dash (20240405 946))) "A Git porcelain inside Emacs." tar nil])
+ (magit-section . [(4 0 0) nil "Sections for read-only buffers." tar nil]))
+"#;
+    assert_eq!(find_version(contents, "ace-window").unwrap(), "0.10.0");
+    assert_eq!(find_version(contents, "magit").unwrap(), "20240426.2118");
+    assert_eq!(find_version(contents, "magit-section").unwrap(), "4.0.0");
+    assert!(find_version(contents, "avy").is_none());
+}
+
+#[test]
+fn test_elpa() {
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "magit".to_string());
+    options.insert("archive".to_string(), "melpa-stable".to_string());
+    let client = Client::new();
+    let checker = ElpaChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
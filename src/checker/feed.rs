@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_text, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::Client;
+
+pub(crate) struct FeedChecker {
+    url: String,
+    field: String,
+    pattern: Option<String>,
+    order: VersionOrder,
+}
+
+/// Decodes the CDATA sections and the predefined XML entities
+fn decode_text(text: &str) -> String {
+    let text = text.trim();
+    if let Some(cdata) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+    {
+        return cdata.trim().to_string();
+    }
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Collects the given field of each Atom `<entry>` or RSS `<item>` in the feed
+fn collect_fields(body: &str, field: &str) -> Result<Vec<String>> {
+    let entries = Regex::new(r"(?s)<(entry|item)(?:\s[^>]*)?>(.*?)</(?:entry|item)>")?;
+    let field = Regex::new(&format!(
+        r"(?s)<{0}(?:\s[^>]*)?>(.*?)</{0}>",
+        regex::escape(field)
+    ))?;
+
+    Ok(entries
+        .captures_iter(body)
+        .filter_map(|entry| {
+            field
+                .captures(entry.get(2)?.as_str())
+                .map(|c| decode_text(&c[1]))
+        })
+        .collect())
+}
+
+impl UpdateChecker for FeedChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let field = config.get("field").map(|s| s.as_str()).unwrap_or("title");
+        if !["title", "id", "guid"].contains(&field) {
+            return Err(anyhow!("Unsupported feed field: {}", field));
+        }
+
+        Ok(FeedChecker {
+            url: must_have!(config, "url", "Feed URL")?.to_string(),
+            field: field.to_string(),
+            pattern: pattern_from_config(config)?,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        self.url.clone()
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(&self.url))?;
+        resp.error_for_status_ref()?;
+        let mut versions = collect_fields(&read_text(resp)?, &self.field)?;
+        debug!("returned entries: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!("No feed entry matches the pattern."));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+#[test]
+fn test_collect_fields() {
+    let atom = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="en-US">
+  <id>tag:github.com,2008:https://github.com/AOSC-Dev/ciel-rs/releases</id>
+  <title>Release notes from ciel-rs</title>
+  <entry>
+    <id>tag:github.com,2008:Repository/123/v3.1.0</id>
+    <title>v3.1.0</title>
+  </entry>
+  <entry>
+    <id>tag:github.com,2008:Repository/123/v3.0.9</id>
+    <title type="html">Ciel &amp; friends v3.0.9</title>
+  </entry>
+</feed>"#;
+    assert_eq!(
+        collect_fields(atom, "title").unwrap(),
+        vec!["v3.1.0", "Ciel & friends v3.0.9"]
+    );
+    assert_eq!(
+        collect_fields(atom, "id").unwrap(),
+        vec![
+            "tag:github.com,2008:Repository/123/v3.1.0",
+            "tag:github.com,2008:Repository/123/v3.0.9"
+        ]
+    );
+    let rss = r#"<rss version="2.0"><channel><title>foo releases</title>
+<item><title><![CDATA[foo 2.0 released]]></title><guid>https://example.com/2.0</guid></item>
+<item><title>foo 1.9 released</title></item>
+</channel></rss>"#;
+    assert_eq!(
+        collect_fields(rss, "title").unwrap(),
+        vec!["foo 2.0 released", "foo 1.9 released"]
+    );
+    assert_eq!(
+        extract_versions(
+            r"^foo (\S+) released$",
+            &collect_fields(rss, "title").unwrap()
+        )
+        .unwrap(),
+        vec!["2.0", "1.9"]
+    );
+}
+
+#[test]
+fn test_feed() {
+    let mut options = HashMap::new();
+    options.insert(
+        "url".to_string(),
+        "https://github.com/AOSC-Dev/ciel-rs/tags.atom".to_string(),
+    );
+    options.insert("pattern".to_string(), r"^v(\d.+)$".to_string());
+    let client = Client::new();
+    let checker = FeedChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -1,8 +1,12 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
-use super::{extract_versions, version_compare, UpdateChecker};
+use super::{
+    extract_versions, git_version, map_versions_to_commits, pattern_from_config, returns_commit,
+    send, UpdateChecker, VersionOrder,
+};
 use crate::must_have;
 use anyhow::{anyhow, Result};
+use log::debug;
 use reqwest::blocking::Client;
 use reqwest::header::USER_AGENT;
 use winnow::{
@@ -14,6 +18,50 @@ use winnow::{
 };
 
 const SIMULATED_GIT_VERSION: &str = "2.31.1";
+/// Hosts serving the same repositories over SSH and HTTPS at the same path
+const KNOWN_SSH_HOSTS: &[&str] = &[
+    "github.com",
+    "gitlab.com",
+    "codeberg.org",
+    "bitbucket.org",
+    "git.sr.ht",
+    "gitlab.gnome.org",
+    "gitlab.freedesktop.org",
+    "invent.kde.org",
+    "salsa.debian.org",
+];
+
+/// Converts SSH-style URLs (`git@host:path` or `ssh://`) to the corresponding HTTPS URL
+fn to_https_url(url: &str) -> Result<String> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Ok(url.to_string());
+    }
+    let (host, path) = if let Some(rest) = url.strip_prefix("ssh://") {
+        let (host, path) = rest
+            .split_once('/')
+            .ok_or_else(|| anyhow!("Invalid SSH URL: {}", url))?;
+        // strip the port
+        (host.split(':').next().unwrap_or(host), path)
+    } else if !url.contains("://") {
+        url.split_once(':')
+            .ok_or_else(|| anyhow!("Invalid Git URL: {}", url))?
+    } else {
+        return Err(anyhow!("Unsupported Git URL: {}", url));
+    };
+    // strip the user name
+    let host = host.rsplit('@').next().unwrap_or(host);
+    if !KNOWN_SSH_HOSTS.contains(&host) {
+        return Err(anyhow!(
+            "Unable to convert {} to HTTPS: unknown host {}",
+            url,
+            host
+        ));
+    }
+    let converted = format!("https://{}/{}", host, path.trim_start_matches('/'));
+    debug!("converted {} to {}", url, converted);
+
+    Ok(converted)
+}
 
 // parser-combinators for parsing Git on-wire format
 fn first_tuple<'a>(input: &mut &'a [u8]) -> PResult<&'a [u8]> {
@@ -33,27 +81,94 @@ fn parse_git_manifest<'a>(input: &mut &'a [u8]) -> PResult<Vec<(&'a [u8], &'a [u
 }
 // end of parser-combinators
 
-fn collect_git_tags<'a>(input: &mut &'a [u8]) -> Result<Vec<&'a str>> {
+/// Makes sure the whole advertisement was received, which always ends with a flush packet
+fn validate_advertisement(body: &[u8]) -> Result<()> {
+    if !body.ends_with(b"0000") {
+        return Err(anyhow!(
+            "Incomplete git advertisement: the response does not end with a flush packet"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Collects the (object ID, ref name) pairs from the Git advertisement
+fn collect_git_refs<'a>(input: &mut &'a [u8]) -> Result<Vec<(&'a str, Cow<'a, str>)>> {
     let tuples = parse_git_manifest(input).map_err(|e| anyhow!("Parser error: {:?}", e))?;
-    let tags: Vec<_> = tuples
+    let refs = tuples
         .iter()
         .filter_map(|x| {
-            if x.1.ends_with(&b"^{}"[..]) {
-                None
-            } else if let Some(name) = x.1.strip_prefix(&b"refs/tags/"[..]) {
-                std::str::from_utf8(name).ok()
-            } else {
-                None
+            // the parser only accepts hex digits here
+            let id = std::str::from_utf8(x.0).ok()?;
+            let name = String::from_utf8_lossy(x.1);
+            // strip the pkt-line length prefix (and the flush packet before the first ref)
+            let Some(id) = id.get(id.len().checked_sub(40)?..) else {
+                debug!("skipped ref with malformed object ID: {}", name);
+                return None;
+            };
+            if let Cow::Owned(_) = name {
+                debug!("ref name is not valid UTF-8, decoded lossily: {}", name);
             }
+
+            Some((id, name))
         })
         .collect();
 
-    Ok(tags)
+    Ok(refs)
+}
+
+/// Collects the tag names and the commits they point to
+fn collect_git_tags(refs: &[(&str, Cow<str>)]) -> Vec<(String, String)> {
+    let mut tags: Vec<(String, String)> = Vec::new();
+    for (id, name) in refs {
+        let name = match name.strip_prefix("refs/tags/") {
+            Some(name) => name,
+            None => continue,
+        };
+        if let Some(name) = name.strip_suffix("^{}") {
+            // peeled annotated tag, which points to the actual commit
+            if let Some(tag) = tags.iter_mut().find(|x| x.0 == name) {
+                tag.1 = id.to_string();
+            }
+            continue;
+        }
+        tags.push((name.to_string(), id.to_string()));
+    }
+
+    tags
+}
+
+/// Finds the commit the branch points to, optionally extracting from the `<rev> <ref>` line
+fn find_branch_rev(
+    refs: &[(&str, Cow<str>)],
+    branch: &str,
+    pattern: Option<&str>,
+    short: bool,
+) -> Result<String> {
+    let ref_name = format!("refs/heads/{}", branch);
+    let (id, name) = refs
+        .iter()
+        // the first ref may carry the capabilities after a NUL byte
+        .map(|(id, name)| (id, name.split('\0').next().unwrap_or(name)))
+        .find(|(_, name)| *name == ref_name)
+        .ok_or_else(|| anyhow!("Unable to find branch {}", branch))?;
+    let rev = if short { &id[..7] } else { id };
+    let Some(pattern) = pattern else {
+        return Ok(rev.to_string());
+    };
+    let line = format!("{} {}", rev, name);
+    extract_versions(pattern, &[&line])?
+        .pop()
+        .ok_or_else(|| anyhow!("Pattern {} does not match {}", pattern, line))
 }
 
 pub(crate) struct GitChecker {
     url: String,
     pattern: Option<String>,
+    return_commit: bool,
+    branch: Option<String>,
+    short: bool,
+    order: VersionOrder,
 }
 
 impl UpdateChecker for GitChecker {
@@ -61,34 +176,70 @@ impl UpdateChecker for GitChecker {
     where
         Self: Sized + UpdateChecker,
     {
-        let url = must_have!(config, "url", "Repository URL")?.to_string();
-        let pattern = config.get("pattern").cloned();
+        let url = to_https_url(must_have!(config, "url", "Repository URL")?)?;
+        let pattern = pattern_from_config(config)?;
+        let return_commit = returns_commit(config)?;
+        let branch = config.get("branch").cloned();
+        let short = config.get("short").map(|s| s == "true").unwrap_or(false);
 
-        Ok(GitChecker { url, pattern })
+        Ok(GitChecker {
+            url,
+            pattern,
+            return_commit,
+            branch,
+            short,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/info/refs?service=git-upload-pack", self.url)
     }
 
     fn check(&self, client: &Client) -> Result<String> {
         // this check method uses a fake Git client implementation
-        let resp = client
-            .get(format!("{}/info/refs?service=git-upload-pack", self.url,))
-            .header(USER_AGENT, format!("git/{}", SIMULATED_GIT_VERSION))
-            .header("git-protocol", "version=2")
-            .send()?;
+        let resp = send(
+            client,
+            client
+                .get(self.endpoint())
+                .header(
+                    USER_AGENT,
+                    format!("git/{}", git_version(SIMULATED_GIT_VERSION)),
+                )
+                .header("git-protocol", "version=2"),
+        )?;
         resp.error_for_status_ref()?;
-        let body = resp.bytes()?;
-        let mut tags = collect_git_tags(&mut body.to_vec().as_ref())?
-            .into_iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<_>>();
+        // the body may be chunked, so make sure all of it was received
+        let body = resp
+            .bytes()
+            .map_err(|e| anyhow!("Incomplete git advertisement: {}", e))?;
+        validate_advertisement(&body)?;
+        let refs = collect_git_refs(&mut body.as_ref())?;
+        debug!("returned refs: {:?}", refs);
+        if let Some(branch) = &self.branch {
+            return find_branch_rev(&refs, branch, self.pattern.as_deref(), self.short);
+        }
+        let git_tags = collect_git_tags(&refs);
+        let mut tags = git_tags.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
         if let Some(pattern) = &self.pattern {
             tags = extract_versions(pattern, &tags)?;
         }
+        debug!("after filter: {:?}", tags);
         if tags.is_empty() {
             return Err(anyhow!("Git ({}) didn't return any tags!", self.url));
         }
-        tags.sort_unstable_by(|b, a| version_compare(a, b));
+        self.order.sort(&mut tags)?;
+        debug!("after sort: {:?}", tags);
+        let tag = tags.first().unwrap();
+        if self.return_commit {
+            let commits = map_versions_to_commits(self.pattern.as_deref(), &git_tags)?;
+            return commits
+                .get(tag)
+                .cloned()
+                .ok_or_else(|| anyhow!("Unable to find the commit of tag {}", tag));
+        }
 
-        Ok(tags.first().unwrap().to_string())
+        Ok(tag.to_string())
     }
 }
 
@@ -154,6 +305,115 @@ fn test_multiline() {
     assert_eq!(test, &mut &b"0000"[..]);
 }
 
+#[test]
+fn test_to_https_url() {
+    assert_eq!(
+        to_https_url("https://git.tuxfamily.org/bluebird/cms.git").unwrap(),
+        "https://git.tuxfamily.org/bluebird/cms.git"
+    );
+    assert_eq!(
+        to_https_url("git@github.com:AOSC-Dev/ciel-rs.git").unwrap(),
+        "https://github.com/AOSC-Dev/ciel-rs.git"
+    );
+    assert_eq!(
+        to_https_url("ssh://git@gitlab.gnome.org:2222/GNOME/fractal.git").unwrap(),
+        "https://gitlab.gnome.org/GNOME/fractal.git"
+    );
+    assert_eq!(
+        to_https_url("git@git.sr.ht:~sircmpwn/scdoc").unwrap(),
+        "https://git.sr.ht/~sircmpwn/scdoc"
+    );
+    assert!(to_https_url("git@git.example.com:foo/bar.git").is_err());
+    assert!(to_https_url("git://git.example.com/foo/bar.git").is_err());
+}
+
+#[test]
+fn test_collect_git_tags() {
+    let test = &mut &b"001e# service=git-upload-pack\n\
+000000fe68e3802b238b964900acac9422a70e295482243f HEAD\x00multi_ack agent=git/2.11.4.GIT\n\
+003f68e3802b238b964900acac9422a70e295482243f refs/heads/master\n\
+003e1111111111111111111111111111111111111111 refs/tags/v1.0\n\
+00412222222222222222222222222222222222222222 refs/tags/v1.0^{}\n\
+003e3333333333333333333333333333333333333333 refs/tags/v1.1\n\
+0000"[..];
+    let refs = collect_git_refs(test).unwrap();
+    assert_eq!(refs.len(), 5);
+    assert_eq!(
+        (refs[1].0, refs[1].1.as_ref()),
+        (
+            "68e3802b238b964900acac9422a70e295482243f",
+            "refs/heads/master"
+        )
+    );
+    assert_eq!(
+        collect_git_tags(&refs),
+        vec![
+            (
+                "v1.0".to_string(),
+                "2222222222222222222222222222222222222222".to_string()
+            ),
+            (
+                "v1.1".to_string(),
+                "3333333333333333333333333333333333333333".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_validate_advertisement() {
+    let body = b"001e# service=git-upload-pack\n\
+000000fe68e3802b238b964900acac9422a70e295482243f HEAD\x00multi_ack agent=git/2.11.4.GIT\n\
+003e1111111111111111111111111111111111111111 refs/tags/v1.0\n\
+0000";
+    assert!(validate_advertisement(body).is_ok());
+    // truncated in the middle of a ref
+    assert!(validate_advertisement(&body[..body.len() - 30]).is_err());
+    // truncated in the middle of the flush packet
+    assert!(validate_advertisement(&body[..body.len() - 2]).is_err());
+    assert!(validate_advertisement(b"").is_err());
+}
+
+#[test]
+fn test_collect_git_refs_lossy() {
+    let test = &mut &b"001e# service=git-upload-pack\n\
+003e1111111111111111111111111111111111111111 refs/tags/v1.0\n\
+003e2222222222222222222222222222222222222222 refs/tags/v\xff.1\n\
+003e3333333333333333333333333333333333333333 refs/tags/v1.2\n\
+0000"[..];
+    let refs = collect_git_refs(test).unwrap();
+    assert_eq!(refs.len(), 3);
+    assert_eq!(refs[1].1, "refs/tags/v\u{fffd}.1");
+    let tags = collect_git_tags(&refs);
+    assert_eq!(tags[0].0, "v1.0");
+    assert_eq!(tags[2].0, "v1.2");
+}
+
+#[test]
+fn test_find_branch_rev() {
+    let test = &mut &b"001e# service=git-upload-pack\n\
+000000fe68e3802b238b964900acac9422a70e295482243f HEAD\x00multi_ack agent=git/2.11.4.GIT\n\
+003f68e3802b238b964900acac9422a70e295482243f refs/heads/master\n\
+00401111111111111111111111111111111111111111 refs/heads/stable\n\
+003e2222222222222222222222222222222222222222 refs/tags/v1.0\n\
+0000"[..];
+    let refs = collect_git_refs(test).unwrap();
+    assert_eq!(
+        find_branch_rev(&refs, "master", None, false).unwrap(),
+        "68e3802b238b964900acac9422a70e295482243f"
+    );
+    assert_eq!(
+        find_branch_rev(&refs, "master", None, true).unwrap(),
+        "68e3802"
+    );
+    assert_eq!(
+        find_branch_rev(&refs, "stable", Some(r"^(\w+) refs/heads/stable$"), true).unwrap(),
+        "1111111"
+    );
+    assert!(find_branch_rev(&refs, "stable", Some("^0"), false).is_err());
+    assert!(find_branch_rev(&refs, "v1.0", None, false).is_err());
+}
+
 #[test]
 fn test_git_raw() {
     let mut options = HashMap::new();
@@ -165,3 +425,16 @@ fn test_git_raw() {
     let checker = GitChecker::new(&options).unwrap();
     dbg!(checker.check(&client).unwrap());
 }
+
+#[test]
+fn test_git_raw_commit() {
+    let mut options = HashMap::new();
+    options.insert(
+        "url".to_string(),
+        "https://git.tuxfamily.org/bluebird/cms.git".to_string(),
+    );
+    options.insert("return".to_string(), "commit".to_string());
+    let client = Client::new();
+    let checker = GitChecker::new(&options).unwrap();
+    assert_eq!(checker.check(&client).unwrap().len(), 40);
+}
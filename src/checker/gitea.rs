@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://codeberg.org";
+/// Number of tags or releases fetched, the API returns the newest first
+const PAGE_LIMIT: usize = 50;
+
+#[derive(Deserialize)]
+struct GiteaTag {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaRelease {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+pub(crate) struct GiteaChecker {
+    instance: String,
+    repo: String,
+    pattern: Option<String>,
+    sort_version: bool,
+    use_releases: bool,
+    include_prerelease: bool,
+    order: VersionOrder,
+}
+
+impl GiteaChecker {
+    /// Keeps the tag names of the published releases, and the pre-releases if asked to
+    fn release_tags(&self, releases: Vec<GiteaRelease>) -> Vec<String> {
+        releases
+            .into_iter()
+            .filter(|x| self.include_prerelease || !(x.draft || x.prerelease))
+            .map(|x| x.tag_name)
+            .collect()
+    }
+
+    fn select_version(&self, mut tags: Vec<String>) -> Result<String> {
+        debug!("returned tags: {:?}", tags);
+        if let Some(pattern) = &self.pattern {
+            tags = extract_versions(pattern, &tags)?;
+        }
+        debug!("after filter: {:?}", tags);
+        if tags.is_empty() {
+            return Err(anyhow!(
+                "Gitea ({}) didn't return any {}!",
+                self.instance,
+                if self.use_releases {
+                    "releases"
+                } else {
+                    "tags"
+                }
+            ));
+        }
+        if self.sort_version || self.order.implies_sort() {
+            self.order.sort(&mut tags)?;
+            debug!("after sort: {:?}", tags);
+        }
+
+        Ok(tags.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for GiteaChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let repo = must_have!(config, "repo", "Repository slug")?.to_string();
+        if repo.split('/').count() != 2 || repo.split('/').any(|x| x.is_empty()) {
+            return Err(anyhow!(
+                "Invalid repository slug {}, expected owner/repo",
+                repo
+            ));
+        }
+        let instance = config
+            .get("instance")
+            .map(|s| s.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| API_ENDPOINT.to_string());
+        let sort_version = config
+            .get("sort_version")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        let use_releases = config
+            .get("use_releases")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        let include_prerelease = config
+            .get("prerelease")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(GiteaChecker {
+            instance,
+            repo,
+            pattern: pattern_from_config(config)?,
+            sort_version,
+            use_releases,
+            include_prerelease,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!(
+            "{}/api/v1/repos/{}/{}?limit={}",
+            self.instance,
+            self.repo,
+            if self.use_releases {
+                "releases"
+            } else {
+                "tags"
+            },
+            PAGE_LIMIT
+        )
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(self.endpoint()))?;
+        resp.error_for_status_ref()?;
+        let tags = if self.use_releases {
+            self.release_tags(read_json(resp)?)
+        } else {
+            read_json::<Vec<GiteaTag>>(resp)?
+                .into_iter()
+                .map(|x| x.name)
+                .collect()
+        };
+
+        self.select_version(tags)
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let releases: Vec<GiteaRelease> = serde_json::from_str(
+        r#"[
+            {"id": 4, "tag_name": "v2.0.0", "draft": true, "prerelease": false},
+            {"id": 3, "tag_name": "v1.10.0-rc1", "draft": false, "prerelease": true},
+            {"id": 2, "tag_name": "v1.9.2", "draft": false, "prerelease": false},
+            {"id": 1, "tag_name": "v1.10.0", "draft": false, "prerelease": false}
+        ]"#,
+    )
+    .unwrap();
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "forgejo/forgejo".to_string());
+    options.insert("use_releases".to_string(), "true".to_string());
+    let checker = GiteaChecker::new(&options).unwrap();
+    assert_eq!(
+        checker.endpoint(),
+        "https://codeberg.org/api/v1/repos/forgejo/forgejo/releases?limit=50"
+    );
+    let tags = checker.release_tags(releases);
+    assert_eq!(tags, ["v1.9.2", "v1.10.0"]);
+    assert_eq!(checker.select_version(tags.clone()).unwrap(), "v1.9.2");
+    options.insert("sort_version".to_string(), "true".to_string());
+    let checker = GiteaChecker::new(&options).unwrap();
+    assert_eq!(checker.select_version(tags).unwrap(), "v1.10.0");
+    options.insert("prerelease".to_string(), "true".to_string());
+    options.insert("pattern".to_string(), r"^v(\d+\.\d+\.\d+)".to_string());
+    let checker = GiteaChecker::new(&options).unwrap();
+    let releases = vec![GiteaRelease {
+        tag_name: "v1.11.0-rc1".to_string(),
+        draft: false,
+        prerelease: true,
+    }];
+    assert_eq!(
+        checker
+            .select_version(checker.release_tags(releases))
+            .unwrap(),
+        "1.11.0"
+    );
+    // tags are the default
+    options.remove("use_releases");
+    options.insert(
+        "instance".to_string(),
+        "https://gitea.example.com/".to_string(),
+    );
+    let checker = GiteaChecker::new(&options).unwrap();
+    assert_eq!(
+        checker.endpoint(),
+        "https://gitea.example.com/api/v1/repos/forgejo/forgejo/tags?limit=50"
+    );
+    assert!(checker.select_version(vec![]).is_err());
+    options.insert("repo".to_string(), "forgejo".to_string());
+    assert!(GiteaChecker::new(&options).is_err());
+}
+
+#[test]
+fn test_gitea() {
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "forgejo/forgejo".to_string());
+    options.insert("pattern".to_string(), r"^v(\d+\.\d+\.\d+)$".to_string());
+    options.insert("sort_version".to_string(), "true".to_string());
+    let client = Client::new();
+    let checker = GiteaChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+    options.insert("use_releases".to_string(), "true".to_string());
+    let checker = GiteaChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -1,16 +1,27 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use super::{extract_versions, version_compare, UpdateChecker};
+use super::{
+    extract_versions, map_versions_to_commits, pattern_from_config, read_json, returns_commit,
+    send, user_agent, UpdateChecker, VersionOrder, DEFAULT_USER_AGENT,
+};
 use crate::must_have;
 use anyhow::{anyhow, Result};
-use log::debug;
+use chrono::{DateTime, Local};
+use log::{debug, warn};
+use regex::Regex;
 use reqwest::blocking::Client;
-use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::{HeaderMap, ACCEPT, AUTHORIZATION, USER_AGENT};
+use reqwest::StatusCode;
 use sailfish::TemplateOnce;
 use serde::{Deserialize, Serialize};
 
 const API_ENDPOINT: &str = "https://api.github.com/";
 
+/// Whether the low rate limit warning has been shown, which is only done once per run
+static RATE_LIMIT_WARNED: AtomicBool = AtomicBool::new(false);
+
 #[derive(TemplateOnce)]
 #[template(path = "github.stpl")]
 struct GitHubQuery {
@@ -23,9 +34,22 @@ struct GitHubRequest {
     query: String,
 }
 
+#[derive(Deserialize)]
+struct GitHubTagTarget {
+    oid: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubTarget {
+    oid: String,
+    // only present for annotated tags, which point to the actual commit
+    target: Option<GitHubTagTarget>,
+}
+
 #[derive(Deserialize)]
 struct GitHubTagData {
     name: String,
+    target: GitHubTarget,
 }
 
 #[derive(Deserialize)]
@@ -48,32 +72,189 @@ struct GitHubResponse {
     data: GitHubResponseInner,
 }
 
+#[derive(Deserialize)]
+struct GitHubRepoInfo {
+    default_branch: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubCommit {
+    sha: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    name: Option<String>,
+    draft: bool,
+    prerelease: bool,
+}
+
+/// Whether the version has a pre-release suffix (e.g. `1.5.0-rc1`, `2.0beta` or `3.0.dev2`)
+fn is_prerelease(version: &str) -> bool {
+    let pattern = Regex::new(r"(?i)(?:\d|[-_.+~])(?:rc|alpha|beta|dev|pre)").unwrap();
+    pattern.is_match(version)
+}
+
+/// Collects the names of the published releases, using the release title for untagged releases
+fn release_names(releases: Vec<GitHubRelease>, include_prerelease: bool) -> Vec<String> {
+    releases
+        .into_iter()
+        .filter(|r| !r.draft && (include_prerelease || !r.prerelease))
+        .filter_map(|r| {
+            if r.tag_name.is_empty() {
+                r.name
+            } else {
+                Some(r.tag_name)
+            }
+        })
+        .collect()
+}
+
+/// Reads a numeric rate limit header
+fn rate_limit_header(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Explains the error if the request was rejected because the rate limit is exhausted
+fn rate_limit_error(status: StatusCode, headers: &HeaderMap) -> Option<String> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    if rate_limit_header(headers, "x-ratelimit-remaining")? != 0 {
+        return None;
+    }
+    let reset = rate_limit_header(headers, "x-ratelimit-reset")
+        .and_then(|t| DateTime::from_timestamp(t as i64, 0))
+        .map(|t| {
+            t.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "an unknown time".to_string());
+
+    Some(format!(
+        "GitHub API rate limit exceeded, the limit resets at {}.{}",
+        reset,
+        if std::env::var("GITHUB_TOKEN").is_ok() {
+            ""
+        } else {
+            " Set GITHUB_TOKEN environment variable for a higher limit."
+        }
+    ))
+}
+
+/// Checks the rate limit headers of the response, warning once if few requests are left
+fn check_rate_limit(resp: &Response) -> Result<()> {
+    let headers = resp.headers();
+    if let Some(error) = rate_limit_error(resp.status(), headers) {
+        return Err(anyhow!(error));
+    }
+    if let (Some(remaining), Some(limit)) = (
+        rate_limit_header(headers, "x-ratelimit-remaining"),
+        rate_limit_header(headers, "x-ratelimit-limit"),
+    ) {
+        debug!("GitHub API rate limit: {}/{} remaining", remaining, limit);
+        if remaining * 10 < limit && !RATE_LIMIT_WARNED.swap(true, Ordering::Relaxed) {
+            warn!(
+                "Only {} of {} GitHub API requests left, expect the checks to be throttled",
+                remaining, limit
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the branch name asks for the default branch of the repository
+fn is_default_branch(branch: &str) -> bool {
+    branch == "HEAD" || branch == "default"
+}
+
 pub(crate) struct GitHubChecker {
     repo: String,
     pattern: Option<String>,
     sort_version: bool,
+    return_commit: bool,
+    branch: Option<String>,
+    use_releases: bool,
+    include_prerelease: bool,
+    stable_only: bool,
+    order: VersionOrder,
 }
 
-impl UpdateChecker for GitHubChecker {
-    fn new(config: &HashMap<String, String>) -> Result<Self>
-    where
-        Self: Sized + UpdateChecker,
-    {
-        let repo = must_have!(config, "repo", "Repository slug")?.to_string();
-        let pattern = config.get("pattern").cloned();
-        let sort_version = config
-            .get("sort_version")
-            .map(|s| s == "true")
-            .unwrap_or(false);
+impl GitHubChecker {
+    /// Builds a request to the REST API, which can be accessed anonymously
+    fn rest_request(&self, client: &Client, path: &str) -> RequestBuilder {
+        let builder = client
+            .get(format!("{}repos/{}{}", API_ENDPOINT, self.repo, path))
+            .header(USER_AGENT, user_agent(DEFAULT_USER_AGENT))
+            .header(ACCEPT, "application/vnd.github+json");
+        match std::env::var("GITHUB_TOKEN") {
+            Ok(token) => builder.header(AUTHORIZATION, format!("token {}", token)),
+            Err(_) => builder,
+        }
+    }
 
-        Ok(GitHubChecker {
-            repo,
-            pattern,
-            sort_version,
-        })
+    /// Returns the commit the branch points to
+    fn check_rev(&self, client: &Client, branch: &str) -> Result<String> {
+        let branch = if is_default_branch(branch) {
+            let resp = send(client, self.rest_request(client, ""))?;
+            check_rate_limit(&resp)?;
+            resp.error_for_status_ref()?;
+            let info: GitHubRepoInfo = read_json(resp)?;
+            debug!("default branch: {}", info.default_branch);
+            info.default_branch
+        } else {
+            branch.to_string()
+        };
+        let resp = send(
+            client,
+            self.rest_request(client, &format!("/commits/{}", branch)),
+        )?;
+        check_rate_limit(&resp)?;
+        resp.error_for_status_ref()?;
+        let commit: GitHubCommit = read_json(resp)?;
+
+        Ok(commit.sha)
     }
 
-    fn check(&self, client: &Client) -> Result<String> {
+    /// Filters and sorts (if requested) the names, and returns the selected one
+    fn select(&self, mut names: Vec<String>, kind: &str) -> Result<String> {
+        debug!("returned {}: {:?}", kind, names);
+        if let Some(pattern) = &self.pattern {
+            names = extract_versions(pattern, &names)?;
+        }
+        debug!("after filter: {:?}", names);
+        if self.stable_only {
+            names.retain(|x| !is_prerelease(x));
+            debug!("after skipping pre-releases: {:?}", names);
+        }
+        if names.is_empty() {
+            return Err(anyhow!("GitHub didn't return any {}!", kind));
+        }
+        if self.sort_version || self.order.implies_sort() {
+            self.order.sort(&mut names)?;
+            debug!("after sort: {:?}", names);
+        }
+
+        Ok(names.swap_remove(0))
+    }
+
+    fn check_releases(&self, client: &Client) -> Result<String> {
+        let resp = send(
+            client,
+            self.rest_request(client, "/releases")
+                .query(&[("per_page", "100")]),
+        )?;
+        check_rate_limit(&resp)?;
+        resp.error_for_status_ref()?;
+        let releases: Vec<GitHubRelease> = read_json(resp)?;
+
+        self.select(release_names(releases, self.include_prerelease), "releases")
+    }
+
+    fn check_tags(&self, client: &Client) -> Result<String> {
         let mut slug = self.repo.splitn(2, '/');
         let query = GitHubQuery {
             owner: slug
@@ -88,36 +269,93 @@ impl UpdateChecker for GitHubChecker {
         .render_once()?;
         let mut builder = client
             .post(format!("{}graphql", API_ENDPOINT))
-            .header(USER_AGENT, "AOSCFindUpdate/0.1.0");
+            .header(USER_AGENT, user_agent(DEFAULT_USER_AGENT));
         if let Ok(token) = std::env::var("GITHUB_TOKEN") {
             builder = builder.header(AUTHORIZATION, format!("token {}", token));
         } else {
             return Err(anyhow!("GitHub checker requires authentication! Please set GITHUB_TOKEN environment variable."));
         }
-        let resp = builder.json(&GitHubRequest { query }).send()?;
+        let resp = send(client, builder.json(&GitHubRequest { query }))?;
+        check_rate_limit(&resp)?;
         resp.error_for_status_ref()?;
-        let payload: GitHubResponse = resp.json()?;
-        let mut payload = payload
+        let payload: GitHubResponse = read_json(resp)?;
+        let tags = payload
             .data
             .repository
             .refs
             .nodes
             .into_iter()
-            .map(|node| node.name)
+            .map(|node| {
+                let commit = node.target.target.map_or(node.target.oid, |x| x.oid);
+                (node.name, commit)
+            })
             .collect::<Vec<_>>();
-        debug!("returned tags: {:?}", payload);
-        if let Some(pattern) = &self.pattern {
-            payload = extract_versions(pattern, &payload)?;
+        let tag = self.select(tags.iter().map(|x| x.0.clone()).collect(), "tags")?;
+        if self.return_commit {
+            let commits = map_versions_to_commits(self.pattern.as_deref(), &tags)?;
+            return commits
+                .get(&tag)
+                .cloned()
+                .ok_or_else(|| anyhow!("Unable to find the commit of tag {}", tag));
         }
-        debug!("after filter: {:?}", payload);
-        if payload.is_empty() {
-            return Err(anyhow!("GitHub didn't return any tags!"));
-        }
-        if self.sort_version {
-            payload.sort_unstable_by(|b, a| version_compare(a, b));
+
+        Ok(tag)
+    }
+}
+
+impl UpdateChecker for GitHubChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let repo = must_have!(config, "repo", "Repository slug")?.to_string();
+        let pattern = pattern_from_config(config)?;
+        let sort_version = config
+            .get("sort_version")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        let return_commit = returns_commit(config)?;
+        let branch = config.get("branch").cloned();
+        let use_releases = config
+            .get("use_releases")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        if use_releases && return_commit {
+            return Err(anyhow!("return=commit is not supported with use_releases"));
         }
+        let include_prerelease = config
+            .get("prerelease")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        let stable_only = config
+            .get("stable_only")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(GitHubChecker {
+            repo,
+            pattern,
+            sort_version,
+            return_commit,
+            branch,
+            use_releases,
+            include_prerelease,
+            stable_only,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
 
-        Ok(payload.first().unwrap().clone())
+    fn endpoint(&self) -> String {
+        // the GraphQL API requires authentication, so check the repository page instead
+        format!("https://github.com/{}", self.repo)
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        match &self.branch {
+            Some(branch) => self.check_rev(client, branch),
+            None if self.use_releases => self.check_releases(client),
+            None => self.check_tags(client),
+        }
     }
 }
 
@@ -129,3 +367,121 @@ fn test_github() {
     let checker = GitHubChecker::new(&options).unwrap();
     dbg!(checker.check(&client).unwrap());
 }
+
+#[test]
+fn test_github_commit() {
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "AOSC-Dev/ciel-rs".to_string());
+    options.insert("return".to_string(), "commit".to_string());
+    let client = Client::new();
+    let checker = GitHubChecker::new(&options).unwrap();
+    assert_eq!(checker.check(&client).unwrap().len(), 40);
+}
+
+#[test]
+fn test_github_default_branch() {
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "AOSC-Dev/ciel-rs".to_string());
+    options.insert("branch".to_string(), "default".to_string());
+    let client = Client::new();
+    let checker = GitHubChecker::new(&options).unwrap();
+    assert_eq!(checker.check(&client).unwrap().len(), 40);
+}
+
+#[test]
+fn test_stable_only() {
+    for version in [
+        "1.5.0-rc1",
+        "2.0beta",
+        "3.0.dev2",
+        "v1.0-alpha.1",
+        "4.0pre",
+        "1.2~RC3",
+    ] {
+        assert!(is_prerelease(version), "{}", version);
+    }
+    for version in ["1.4.9", "v2.0.0", "release-1.0", "20240101"] {
+        assert!(!is_prerelease(version), "{}", version);
+    }
+    let tags = [
+        "v1.4.9",
+        "v1.5.0-rc1",
+        "v1.5.0-beta.2",
+        "v1.4.10",
+        "nightly",
+    ]
+    .map(String::from)
+    .to_vec();
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "AOSC-Dev/ciel-rs".to_string());
+    options.insert("pattern".to_string(), r"^v(\d.+)$".to_string());
+    options.insert("sort_version".to_string(), "true".to_string());
+    let checker = GitHubChecker::new(&options).unwrap();
+    assert_eq!(checker.select(tags.clone(), "tags").unwrap(), "1.5.0-rc1");
+    options.insert("stable_only".to_string(), "true".to_string());
+    let checker = GitHubChecker::new(&options).unwrap();
+    assert_eq!(checker.select(tags, "tags").unwrap(), "1.4.10");
+    let tags = vec!["v2.0.0-rc1".to_string()];
+    assert!(checker.select(tags, "tags").is_err());
+}
+
+#[test]
+fn test_release_names() {
+    let releases = r#"[
+        {"tag_name": "v2.0.0-rc.1", "name": "2.0.0 RC 1", "draft": false, "prerelease": true},
+        {"tag_name": "", "name": "v1.9.0", "draft": false, "prerelease": false},
+        {"tag_name": "v1.8.1", "name": null, "draft": true, "prerelease": false},
+        {"tag_name": "nightly", "name": "Nightly build", "draft": false, "prerelease": false},
+        {"tag_name": "v1.8.0", "name": "Version 1.8.0", "draft": false, "prerelease": false}
+    ]"#;
+    let names = release_names(serde_json::from_str(releases).unwrap(), false);
+    assert_eq!(names, vec!["v1.9.0", "nightly", "v1.8.0"]);
+    let names = release_names(serde_json::from_str(releases).unwrap(), true);
+    assert_eq!(names[0], "v2.0.0-rc.1");
+    // `use_releases=true;pattern=^v(.+)$` skips the nightly release
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "AOSC-Dev/ciel-rs".to_string());
+    options.insert("use_releases".to_string(), "true".to_string());
+    options.insert("pattern".to_string(), "^v(.+)$".to_string());
+    let checker = GitHubChecker::new(&options).unwrap();
+    let names = release_names(serde_json::from_str(releases).unwrap(), false);
+    assert_eq!(checker.select(names, "releases").unwrap(), "1.9.0");
+    // `prerelease=true;sort_version=true` picks the release candidate
+    options.insert("prerelease".to_string(), "true".to_string());
+    options.insert("sort_version".to_string(), "true".to_string());
+    let checker = GitHubChecker::new(&options).unwrap();
+    let names = release_names(serde_json::from_str(releases).unwrap(), true);
+    assert_eq!(checker.select(names, "releases").unwrap(), "2.0.0-rc.1");
+    options.insert("return".to_string(), "commit".to_string());
+    assert!(GitHubChecker::new(&options).is_err());
+}
+
+#[test]
+fn test_rate_limit_error() {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-ratelimit-limit", "60".parse().unwrap());
+    headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+    headers.insert("x-ratelimit-reset", "1700000000".parse().unwrap());
+    let error = rate_limit_error(StatusCode::FORBIDDEN, &headers).unwrap();
+    assert!(
+        error.starts_with("GitHub API rate limit exceeded, the limit resets at 2023-11-1"),
+        "{}",
+        error
+    );
+    assert!(rate_limit_error(StatusCode::TOO_MANY_REQUESTS, &headers).is_some());
+    // the request failed for another reason
+    assert!(rate_limit_error(StatusCode::NOT_FOUND, &headers).is_none());
+    headers.insert("x-ratelimit-remaining", "12".parse().unwrap());
+    assert!(rate_limit_error(StatusCode::FORBIDDEN, &headers).is_none());
+    assert!(rate_limit_error(StatusCode::FORBIDDEN, &HeaderMap::new()).is_none());
+}
+
+#[test]
+fn test_github_releases() {
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "AOSC-Dev/ciel-rs".to_string());
+    options.insert("use_releases".to_string(), "true".to_string());
+    let client = Client::new();
+    let checker = GitHubChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use super::{
+    extract_versions, pattern_from_config, read_text, send, strip_xssi_prefix, UpdateChecker,
+    VersionOrder,
+};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use serde_json::Value;
+
+pub(crate) struct GitilesChecker {
+    url: String,
+    pattern: Option<String>,
+    order: VersionOrder,
+}
+
+/// Collects the tag names from the Gitiles `+refs/tags?format=JSON` response
+fn collect_tags(body: &str) -> Result<Vec<String>> {
+    let refs: HashMap<String, Value> = serde_json::from_str(strip_xssi_prefix(body))?;
+    let tags = refs
+        .into_keys()
+        .map(|name| {
+            name.strip_prefix("refs/tags/")
+                .map_or_else(|| name.clone(), |s| s.to_string())
+        })
+        .collect();
+
+    Ok(tags)
+}
+
+impl UpdateChecker for GitilesChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let url = must_have!(config, "url", "Gitiles repository URL")?;
+
+        Ok(GitilesChecker {
+            url: url.trim_end_matches('/').to_string(),
+            pattern: pattern_from_config(config)?,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/+refs/tags?format=JSON", self.url)
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(self.endpoint()))?;
+        resp.error_for_status_ref()?;
+        let mut tags = collect_tags(&read_text(resp)?)?;
+        debug!("returned tags: {:?}", tags);
+        if let Some(pattern) = &self.pattern {
+            tags = extract_versions(pattern, &tags)?;
+        }
+        debug!("after filter: {:?}", tags);
+        if tags.is_empty() {
+            return Err(anyhow!("Gitiles ({}) didn't return any tags!", self.url));
+        }
+        self.order.sort(&mut tags)?;
+
+        Ok(tags.first().unwrap().to_string())
+    }
+}
+
+#[test]
+fn test_collect_tags() {
+    let body = r#")]}'
+{
+  "v1.0": {"value": "1111111111111111111111111111111111111111"},
+  "refs/tags/v1.1": {
+    "value": "2222222222222222222222222222222222222222",
+    "peeled": "3333333333333333333333333333333333333333"
+  }
+}"#;
+    let mut tags = collect_tags(body).unwrap();
+    tags.sort();
+    assert_eq!(tags, vec!["v1.0", "v1.1"]);
+    assert!(collect_tags("<html></html>").is_err());
+}
+
+#[test]
+fn test_gitiles() {
+    let mut options = HashMap::new();
+    options.insert(
+        "url".to_string(),
+        "https://gerrit.googlesource.com/gerrit".to_string(),
+    );
+    options.insert("pattern".to_string(), r"^v(\d+\.\d+\.\d+)$".to_string());
+    let client = Client::new();
+    let checker = GitilesChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -1,18 +1,82 @@
 use std::collections::HashMap;
 
-use super::{extract_versions, version_compare, UpdateChecker};
+use super::{
+    extract_versions, map_versions_to_commits, pattern_from_config, read_text, returns_commit,
+    send, UpdateChecker, VersionOrder,
+};
 use crate::must_have;
 use anyhow::{anyhow, Result};
 use log::debug;
 use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
-use reqwest::blocking::Client;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::{CONTENT_TYPE, LINK};
+use reqwest::{StatusCode, Url};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 
 const API_ENDPOINT: &str = "https://gitlab.com";
+const DEFAULT_MAX_PAGES: usize = 10;
+
+#[derive(Deserialize)]
+struct GitLabCommit {
+    id: String,
+}
 
 #[derive(Deserialize)]
 struct GitLabData {
     name: String,
+    commit: GitLabCommit,
+}
+
+#[derive(Deserialize)]
+struct GitLabRelease {
+    tag_name: String,
+    commit: GitLabCommit,
+}
+
+/// Parses a page of the listing, rejecting HTML error or maintenance pages
+fn parse_page<T: DeserializeOwned>(content_type: &str, body: &str) -> Result<Vec<T>> {
+    if !content_type.contains("json") || body.trim_start().starts_with('<') {
+        return Err(anyhow!(
+            "GitLab returned a non-JSON response (content type: {}), the instance may be down or under maintenance",
+            content_type
+        ));
+    }
+
+    Ok(serde_json::from_str(body)?)
+}
+
+/// Name of the variable holding the token for a specific instance, e.g. `GITLAB_TOKEN_GITLAB_GNOME_ORG`
+fn token_variable(host: &str) -> String {
+    let host = host
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    format!("GITLAB_TOKEN_{}", host)
+}
+
+/// Extracts the URL of the next page from the `Link` response header
+fn next_page(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        if params.split(';').any(|p| p.trim() == r#"rel="next""#) {
+            Some(
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string(),
+            )
+        } else {
+            None
+        }
+    })
 }
 
 pub(crate) struct GitLabChecker {
@@ -20,6 +84,79 @@ pub(crate) struct GitLabChecker {
     repo: String,
     pattern: Option<String>,
     sort_version: bool,
+    return_commit: bool,
+    use_releases: bool,
+    max_pages: usize,
+    order: VersionOrder,
+}
+
+impl GitLabChecker {
+    /// Looks up the access token, preferring the one specific to this instance
+    fn token(&self) -> Option<String> {
+        Url::parse(&self.instance)
+            .ok()
+            .and_then(|url| url.host_str().map(token_variable))
+            .and_then(|name| std::env::var(name).ok())
+            .or_else(|| std::env::var("GITLAB_TOKEN").ok())
+    }
+
+    fn authorize(&self, builder: RequestBuilder, token: Option<&str>) -> RequestBuilder {
+        if let Some(token) = token {
+            builder.header("PRIVATE-TOKEN", token)
+        } else {
+            builder
+        }
+    }
+
+    /// Fetches the listing page by page, until the last page or `max_pages` is reached
+    fn fetch_pages<T: DeserializeOwned>(&self, client: &Client) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let token = self.token();
+        let mut url = Some(format!("{}?per_page=100", self.endpoint()));
+        for _ in 0..self.max_pages {
+            let Some(current) = url.take() else {
+                break;
+            };
+            let resp = send(
+                client,
+                self.authorize(client.get(&current), token.as_deref()),
+            )?;
+            let status = resp.status();
+            if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+                return Err(anyhow!(
+                    "GitLab ({}) denied access to {} ({}), {}",
+                    self.instance,
+                    self.repo,
+                    status,
+                    if token.is_some() {
+                        "please check that the token has the read_api scope and access to the project."
+                    } else {
+                        "the project may be private. Please set GITLAB_TOKEN environment variable."
+                    }
+                ));
+            }
+            resp.error_for_status_ref()?;
+            let headers = resp.headers();
+            let content_type = headers
+                .get(CONTENT_TYPE)
+                .and_then(|x| x.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            url = headers
+                .get(LINK)
+                .and_then(|x| x.to_str().ok())
+                .and_then(next_page);
+            items.extend(parse_page(&content_type, &read_text(resp)?)?);
+        }
+        if url.is_some() {
+            debug!(
+                "stopped after {} pages, increase max_pages to fetch more",
+                self.max_pages
+            );
+        }
+
+        Ok(items)
+    }
 }
 
 impl UpdateChecker for GitLabChecker {
@@ -32,30 +169,62 @@ impl UpdateChecker for GitLabChecker {
             .get("instance")
             .cloned()
             .unwrap_or_else(|| API_ENDPOINT.to_string());
-        let pattern = config.get("pattern").cloned();
+        let pattern = pattern_from_config(config)?;
         let sort_version = config
             .get("sort_version")
             .map(|s| s == "true")
             .unwrap_or(false);
+        let return_commit = returns_commit(config)?;
+        let use_releases = config
+            .get("use_releases")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        let max_pages = match config.get("max_pages") {
+            Some(n) => match n.parse::<usize>() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(anyhow!("Invalid max_pages: {}", n)),
+            },
+            None => DEFAULT_MAX_PAGES,
+        };
 
         Ok(GitLabChecker {
             instance,
             repo,
             pattern,
             sort_version,
+            return_commit,
+            use_releases,
+            max_pages,
+            order: VersionOrder::from_config(config)?,
         })
     }
 
+    fn endpoint(&self) -> String {
+        format!(
+            "{}/api/v4/projects/{}/{}",
+            self.instance,
+            percent_encode(self.repo.as_bytes(), NON_ALPHANUMERIC),
+            if self.use_releases {
+                "releases"
+            } else {
+                "repository/tags"
+            }
+        )
+    }
+
     fn check(&self, client: &Client) -> Result<String> {
-        let resp = client
-            .get(format!(
-                "{}/api/v4/projects/{}/repository/tags",
-                self.instance,
-                percent_encode(self.repo.as_bytes(), NON_ALPHANUMERIC)
-            ))
-            .send()?;
-        let payload: Vec<GitLabData> = resp.json()?;
-        let mut payload = payload.into_iter().map(|x| x.name).collect::<Vec<_>>();
+        let tags = if self.use_releases {
+            self.fetch_pages::<GitLabRelease>(client)?
+                .into_iter()
+                .map(|x| (x.tag_name, x.commit.id))
+                .collect::<Vec<_>>()
+        } else {
+            self.fetch_pages::<GitLabData>(client)?
+                .into_iter()
+                .map(|x| (x.name, x.commit.id))
+                .collect::<Vec<_>>()
+        };
+        let mut payload = tags.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
         debug!("returned tags: {:?}", payload);
         if let Some(pattern) = &self.pattern {
             payload = extract_versions(pattern, &payload)?;
@@ -63,18 +232,78 @@ impl UpdateChecker for GitLabChecker {
         debug!("after filter: {:?}", payload);
         if payload.is_empty() {
             return Err(anyhow!(
-                "GitLab ({}) didn't return any tags!",
-                self.instance
+                "GitLab ({}) didn't return any {}!",
+                self.instance,
+                if self.use_releases {
+                    "releases"
+                } else {
+                    "tags"
+                }
             ));
         }
-        if self.sort_version {
-            payload.sort_unstable_by(|b, a| version_compare(a, b));
+        if self.sort_version || self.order.implies_sort() {
+            self.order.sort(&mut payload)?;
+            debug!("after sort: {:?}", payload);
+        }
+        let tag = payload.first().unwrap();
+        if self.return_commit {
+            let commits = map_versions_to_commits(self.pattern.as_deref(), &tags)?;
+            return commits
+                .get(tag)
+                .cloned()
+                .ok_or_else(|| anyhow!("Unable to find the commit of tag {}", tag));
         }
 
-        Ok(payload.first().unwrap().clone())
+        Ok(tag.clone())
     }
 }
 
+#[test]
+fn test_parse_page() {
+    let tags: Vec<GitLabData> = parse_page(
+        "application/json",
+        r#"[{"name":"v1.0","commit":{"id":"aaaa"}}]"#,
+    )
+    .unwrap();
+    assert_eq!(tags[0].name, "v1.0");
+    assert_eq!(tags[0].commit.id, "aaaa");
+    let err = parse_page::<GitLabData>(
+        "text/html; charset=utf-8",
+        "<!DOCTYPE html><html><body>GitLab is under maintenance</body></html>",
+    )
+    .err()
+    .unwrap();
+    assert!(err.to_string().contains("non-JSON response"));
+    assert!(parse_page::<GitLabData>("application/json", "<html></html>").is_err());
+}
+
+#[test]
+fn test_token_variable() {
+    assert_eq!(token_variable("gitlab.com"), "GITLAB_TOKEN_GITLAB_COM");
+    assert_eq!(
+        token_variable("gitlab.gnome.org"),
+        "GITLAB_TOKEN_GITLAB_GNOME_ORG"
+    );
+    assert_eq!(
+        token_variable("git-lab.example.com"),
+        "GITLAB_TOKEN_GIT_LAB_EXAMPLE_COM"
+    );
+}
+
+#[test]
+fn test_next_page() {
+    let link = r#"<https://gitlab.gnome.org/api/v4/projects/GNOME%2Fgtk/repository/tags?page=1&per_page=100>; rel="prev", <https://gitlab.gnome.org/api/v4/projects/GNOME%2Fgtk/repository/tags?page=3&per_page=100>; rel="next", <https://gitlab.gnome.org/api/v4/projects/GNOME%2Fgtk/repository/tags?page=1&per_page=100>; rel="first""#;
+    assert_eq!(
+        next_page(link).unwrap(),
+        "https://gitlab.gnome.org/api/v4/projects/GNOME%2Fgtk/repository/tags?page=3&per_page=100"
+    );
+    assert!(
+        next_page(r#"<https://gitlab.com/api/v4/projects/1/releases?page=1>; rel="first""#)
+            .is_none()
+    );
+    assert!(next_page("").is_none());
+}
+
 #[test]
 fn test_gnome() {
     let mut options = HashMap::new();
@@ -87,3 +316,44 @@ fn test_gnome() {
     let checker = GitLabChecker::new(&options).unwrap();
     dbg!(checker.check(&client).unwrap());
 }
+
+#[test]
+fn test_gnome_commit() {
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "GNOME/fractal".to_string());
+    options.insert(
+        "instance".to_string(),
+        "https://gitlab.gnome.org".to_string(),
+    );
+    options.insert("return".to_string(), "commit".to_string());
+    let client = Client::new();
+    let checker = GitLabChecker::new(&options).unwrap();
+    assert_eq!(checker.check(&client).unwrap().len(), 40);
+}
+
+#[test]
+fn test_gnome_many_tags() {
+    // GTK has well over a thousand tags, the stable 4.x ones are not on the first page
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "GNOME/gtk".to_string());
+    options.insert(
+        "instance".to_string(),
+        "https://gitlab.gnome.org".to_string(),
+    );
+    options.insert("pattern".to_string(), r"^4\.\d*[02468]\.\d+$".to_string());
+    options.insert("sort_version".to_string(), "true".to_string());
+    options.insert("max_pages".to_string(), "20".to_string());
+    let client = Client::new();
+    let checker = GitLabChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
+
+#[test]
+fn test_gitlab_releases() {
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "fcitx/fcitx5".to_string());
+    options.insert("use_releases".to_string(), "true".to_string());
+    let client = Client::new();
+    let checker = GitLabChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
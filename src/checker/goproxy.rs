@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use super::{
+    extract_versions, pattern_from_config, read_json, read_text, send, UpdateChecker, VersionOrder,
+};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://proxy.golang.org";
+
+#[derive(Deserialize)]
+struct GoModuleInfo {
+    #[serde(rename = "Version")]
+    version: String,
+}
+
+/// Escapes the module path for the proxy protocol: uppercase letters are replaced by `!` followed
+/// by the lowercase letter, since the paths are case-sensitive but file systems may not be
+fn escape_module_path(module: &str) -> String {
+    let mut escaped = String::with_capacity(module.len());
+    for c in module.chars() {
+        if c.is_ascii_uppercase() {
+            escaped.push('!');
+            escaped.push(c.to_ascii_lowercase());
+        } else {
+            escaped.push(c);
+        }
+    }
+
+    escaped
+}
+
+/// Go versions are always prefixed with `v` (e.g. `v0.22.0`)
+fn strip_prefix(version: &str) -> String {
+    version.strip_prefix('v').unwrap_or(version).to_string()
+}
+
+pub(crate) struct GoProxyChecker {
+    module: String,
+    proxy: String,
+    pattern: Option<String>,
+    sort_version: bool,
+    order: VersionOrder,
+}
+
+impl GoProxyChecker {
+    fn module_url(&self) -> String {
+        format!(
+            "{}/{}",
+            self.proxy.trim_end_matches('/'),
+            escape_module_path(&self.module)
+        )
+    }
+
+    fn select_version(&self, list: &str) -> Result<String> {
+        let mut versions = list
+            .lines()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .map(strip_prefix)
+            .collect::<Vec<_>>();
+        debug!("returned versions: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!(
+                "Go module proxy didn't return any matching versions of {}!",
+                self.module
+            ));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for GoProxyChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let module = must_have!(config, "module", "Module path")?.to_string();
+        let proxy = config
+            .get("proxy")
+            .cloned()
+            .unwrap_or_else(|| API_ENDPOINT.to_string());
+        let sort_version = config
+            .get("sort_version")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(GoProxyChecker {
+            module,
+            proxy,
+            pattern: pattern_from_config(config)?,
+            sort_version,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        if self.pattern.is_some() || self.sort_version || self.order.implies_sort() {
+            format!("{}/@v/list", self.module_url())
+        } else {
+            format!("{}/@latest", self.module_url())
+        }
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(self.endpoint()))?;
+        resp.error_for_status_ref()?;
+        // the latest version known to the proxy, which may be a pseudo-version if there is no tag
+        if self.pattern.is_none() && !self.sort_version && !self.order.implies_sort() {
+            let info: GoModuleInfo = read_json(resp)?;
+            return Ok(strip_prefix(&info.version));
+        }
+
+        self.select_version(&read_text(resp)?)
+    }
+}
+
+#[test]
+fn test_escape_module_path() {
+    assert_eq!(
+        escape_module_path("golang.org/x/tools"),
+        "golang.org/x/tools"
+    );
+    assert_eq!(
+        escape_module_path("github.com/BurntSushi/toml"),
+        "github.com/!burnt!sushi/toml"
+    );
+}
+
+#[test]
+fn test_select_version() {
+    let list = "v0.9.1\nv0.10.0\nv0.21.0-pre.1\nv0.21.0\n\n";
+    let mut options = HashMap::new();
+    options.insert("module".to_string(), "golang.org/x/tools".to_string());
+    let checker = GoProxyChecker::new(&options).unwrap();
+    assert!(checker.endpoint().ends_with("/golang.org/x/tools/@latest"));
+    // an explicit order implies `sort_version`
+    options.insert("semver".to_string(), "true".to_string());
+    let checker = GoProxyChecker::new(&options).unwrap();
+    assert!(checker.endpoint().ends_with("/golang.org/x/tools/@v/list"));
+    options.insert("sort_version".to_string(), "true".to_string());
+    let checker = GoProxyChecker::new(&options).unwrap();
+    assert_eq!(checker.select_version(list).unwrap(), "0.21.0");
+    options.insert("pattern".to_string(), r"^0\.9\.".to_string());
+    let checker = GoProxyChecker::new(&options).unwrap();
+    assert_eq!(checker.select_version(list).unwrap(), "0.9.1");
+    assert!(checker.select_version("").is_err());
+}
+
+#[test]
+fn test_goproxy() {
+    let mut options = HashMap::new();
+    options.insert("module".to_string(), "golang.org/x/tools".to_string());
+    let client = Client::new();
+    let checker = GoProxyChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+    options.insert("sort_version".to_string(), "true".to_string());
+    let checker = GoProxyChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
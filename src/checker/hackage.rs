@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::blocking::Client;
+use reqwest::header::ACCEPT;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://hackage.haskell.org/package/";
+
+#[derive(Deserialize)]
+struct HackagePreferred {
+    /// Versions not deprecated by the maintainers, from the highest to the lowest
+    #[serde(rename = "normal-version", default)]
+    normal_version: Vec<String>,
+}
+
+pub(crate) struct HackageChecker {
+    name: String,
+    pattern: Option<String>,
+}
+
+impl HackageChecker {
+    fn select_version(&self, payload: HackagePreferred) -> Result<String> {
+        let mut versions = payload.normal_version;
+        debug!("returned versions: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!("Hackage didn't return any matching versions!"));
+        }
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for HackageChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        Ok(HackageChecker {
+            name: must_have!(config, "name", "Package name")?.to_string(),
+            pattern: pattern_from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!(
+            "{}{}/preferred",
+            API_ENDPOINT,
+            utf8_percent_encode(&self.name, NON_ALPHANUMERIC)
+        )
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(
+            client,
+            client
+                .get(self.endpoint())
+                .header(ACCEPT, "application/json"),
+        )?;
+        resp.error_for_status_ref()?;
+        let payload: HackagePreferred = read_json(resp)?;
+
+        self.select_version(payload)
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let payload = r#"{
+        "normal-version": ["2.2.3.0", "2.2.2.0", "2.1.2.1", "1.5.6.0"],
+        "deprecated-version": ["2.2.0.0"]
+    }"#;
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "aeson".to_string());
+    let checker = HackageChecker::new(&options).unwrap();
+    // the preferred versions come first, and the deprecated ones are ignored
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "2.2.3.0"
+    );
+    assert!(checker
+        .select_version(serde_json::from_str("{}").unwrap())
+        .is_err());
+}
+
+#[test]
+fn test_hackage() {
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "aeson".to_string());
+    let client = Client::new();
+    let checker = HackageChecker::new(&options).unwrap();
+    let version = checker.check(&client).unwrap();
+    assert!(
+        regex::Regex::new(r"^\d+(\.\d+)+$")
+            .unwrap()
+            .is_match(&version),
+        "{}",
+        version
+    );
+}
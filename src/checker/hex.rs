@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use super::{
+    extract_versions, pattern_from_config, read_json, send, user_agent, UpdateChecker,
+    VersionOrder, DEFAULT_USER_AGENT,
+};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://hex.pm/api/packages";
+
+#[derive(Deserialize)]
+struct HexRelease {
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct HexPackage {
+    /// Releases of the package, from the newest to the oldest
+    releases: Vec<HexRelease>,
+}
+
+pub(crate) struct HexChecker {
+    name: String,
+    pattern: Option<String>,
+    stable_only: bool,
+    order: VersionOrder,
+}
+
+impl HexChecker {
+    fn select_version(&self, payload: HexPackage) -> Result<String> {
+        let mut versions = payload
+            .releases
+            .into_iter()
+            .map(|x| x.version)
+            // pre-releases are marked with a `-` (e.g. `1.8.0-rc.0`)
+            .filter(|v| !self.stable_only || !v.contains('-'))
+            .collect::<Vec<_>>();
+        debug!("returned versions: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!(
+                "Hex didn't return any matching versions of {}!",
+                self.name
+            ));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for HexChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let name = must_have!(config, "name", "Package name")?.to_string();
+        let stable_only = config
+            .get("stable_only")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(HexChecker {
+            name,
+            pattern: pattern_from_config(config)?,
+            stable_only,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/{}", API_ENDPOINT, self.name)
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        // the API rejects the requests without a User-Agent
+        let resp = send(
+            client,
+            client
+                .get(self.endpoint())
+                .header(USER_AGENT, user_agent(DEFAULT_USER_AGENT)),
+        )?;
+        resp.error_for_status_ref()?;
+        let payload: HexPackage = read_json(resp)?;
+
+        self.select_version(payload)
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let payload = r#"{
+        "name": "phoenix",
+        "releases": [
+            {"version": "1.8.0-rc.0", "has_docs": true, "url": "https://hex.pm/api/packages/phoenix/releases/1.8.0-rc.0"},
+            {"version": "1.7.14", "has_docs": true, "url": "https://hex.pm/api/packages/phoenix/releases/1.7.14"},
+            {"version": "1.7.9", "has_docs": true, "url": "https://hex.pm/api/packages/phoenix/releases/1.7.9"},
+            {"version": "1.6.16", "has_docs": true, "url": "https://hex.pm/api/packages/phoenix/releases/1.6.16"}
+        ]
+    }"#;
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "phoenix".to_string());
+    let checker = HexChecker::new(&options).unwrap();
+    // pre-releases are included unless asked not to
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "1.8.0-rc.0"
+    );
+    options.insert("stable_only".to_string(), "true".to_string());
+    let checker = HexChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "1.7.14"
+    );
+    assert!(checker
+        .select_version(serde_json::from_str(r#"{"releases": []}"#).unwrap())
+        .is_err());
+}
+
+#[test]
+fn test_hex() {
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "phoenix".to_string());
+    options.insert("stable_only".to_string(), "true".to_string());
+    let client = Client::new();
+    let checker = HexChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -1,16 +1,18 @@
 use std::collections::HashMap;
 
-use super::version_compare;
-use super::UpdateChecker;
+use super::{
+    extract_versions_in_text, pattern_from_config, read_text_bounded, send, UpdateChecker,
+    VersionOrder,
+};
 use crate::must_have;
 use anyhow::{anyhow, Result};
 use log::debug;
-use regex::Regex;
 use reqwest::blocking::Client;
 
 pub(crate) struct HTMLChecker {
     url: String,
     pattern: String,
+    order: VersionOrder,
 }
 
 impl UpdateChecker for HTMLChecker {
@@ -20,41 +22,29 @@ impl UpdateChecker for HTMLChecker {
     {
         Ok(HTMLChecker {
             url: must_have!(config, "url", "HTML URL")?.to_string(),
-            pattern: must_have!(config, "pattern", "Regex pattern for matching versions")?
-                .to_string(),
+            pattern: pattern_from_config(config)?
+                .ok_or_else(|| anyhow!("Please specify Regex pattern for matching versions!"))?,
+            order: VersionOrder::from_config(config)?,
         })
     }
 
+    fn endpoint(&self) -> String {
+        self.url.clone()
+    }
+
     fn check(&self, client: &Client) -> Result<String> {
-        let resp = client.get(&self.url).send()?;
-        if let Some(len) = resp.content_length() {
-            if len > 10 * 1024 * 1024 {
-                // 10 MB
-                return Err(anyhow!("HTML body too large"));
-            }
-        }
+        let resp = send(client, client.get(&self.url))?;
         resp.error_for_status_ref()?;
-        let body = resp.text()?;
-        let pattern = Regex::new(&self.pattern)?;
-        let matches = pattern.captures_iter(&body);
-        let mut versions = Vec::with_capacity(10);
-        for m in matches {
-            versions.push(
-                m.get(1)
-                    .ok_or_else(|| anyhow!("Pattern did not capture anything."))?
-                    .as_str(),
-            );
-        }
+        let body = read_text_bounded(resp)?;
+        let mut versions = extract_versions_in_text(&self.pattern, &body)?;
+        debug!("matched tags: {:?}", versions);
         if versions.is_empty() {
             return Err(anyhow!("No version matches the pattern."));
-        } else if versions.len() == 1 {
-            return Ok(versions[0].to_string());
         }
-        debug!("matched tags: {:?}", versions);
-
-        versions.sort_unstable_by(|a, b| version_compare(a, b));
+        self.order.sort(&mut versions)?;
+        debug!("after sort: {:?}", versions);
 
-        return Ok(versions.last().unwrap().to_string());
+        Ok(versions.swap_remove(0))
     }
 }
 
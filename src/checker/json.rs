@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use super::{
+    extract_versions, pattern_from_config, read_json, send, user_agent, UpdateChecker,
+    VersionOrder, DEFAULT_USER_AGENT,
+};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use serde_json::Value;
+
+pub(crate) struct JsonChecker {
+    url: String,
+    post: bool,
+    body: Option<String>,
+    content_type: String,
+    path: Option<String>,
+    field: Option<String>,
+    pattern: Option<String>,
+    order: VersionOrder,
+}
+
+/// Resolves a dot-separated path (e.g. `data.releases.0`) in the JSON value
+fn resolve_path<'a>(value: &'a Value, path: &str) -> Result<&'a Value> {
+    path.split('.')
+        .filter(|x| !x.is_empty())
+        .try_fold(value, |value, key| {
+            let next = match value {
+                Value::Array(array) => key.parse::<usize>().ok().and_then(|i| array.get(i)),
+                _ => value.get(key),
+            };
+            next.ok_or_else(|| anyhow!("Unable to resolve `{}` in path `{}`", key, path))
+        })
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Collects the version candidates from the value at `path`, selecting `field` from each object
+fn collect_candidates(
+    value: &Value,
+    path: Option<&str>,
+    field: Option<&str>,
+) -> Result<Vec<String>> {
+    let value = resolve_path(value, path.unwrap_or_default())?;
+    let items = match value {
+        Value::Array(array) => array.iter().collect::<Vec<_>>(),
+        _ => vec![value],
+    };
+    let mut candidates = Vec::with_capacity(items.len());
+    for item in items {
+        let item = if let Some(field) = field {
+            resolve_path(item, field)?
+        } else {
+            item
+        };
+        candidates.push(
+            value_to_string(item).ok_or_else(|| {
+                anyhow!("Value is not a string or a number, please specify `field`")
+            })?,
+        );
+    }
+
+    Ok(candidates)
+}
+
+impl UpdateChecker for JsonChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let post = match config
+            .get("method")
+            .map(|m| m.to_ascii_uppercase())
+            .as_deref()
+        {
+            None | Some("GET") => false,
+            Some("POST") => true,
+            Some(m) => return Err(anyhow!("Unsupported HTTP method: {}", m)),
+        };
+        let body = config.get("body").cloned();
+        if body.is_some() && !post {
+            return Err(anyhow!("Request body is only supported with method=POST!"));
+        }
+
+        Ok(JsonChecker {
+            url: must_have!(config, "url", "JSON API URL")?.to_string(),
+            post,
+            body,
+            content_type: config
+                .get("content_type")
+                .cloned()
+                .unwrap_or_else(|| "application/json".to_string()),
+            path: config.get("path").cloned(),
+            field: config.get("field").cloned(),
+            pattern: pattern_from_config(config)?,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        self.url.clone()
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let builder = if self.post {
+            client
+                .post(&self.url)
+                .header(CONTENT_TYPE, &self.content_type)
+                .body(self.body.clone().unwrap_or_default())
+        } else {
+            client.get(&self.url)
+        };
+        // some APIs (e.g. GitHub) reject the requests without a User-Agent
+        let resp = send(
+            client,
+            builder.header(USER_AGENT, user_agent(DEFAULT_USER_AGENT)),
+        )?;
+        resp.error_for_status_ref()?;
+        let payload: Value = read_json(resp)?;
+        let mut versions =
+            collect_candidates(&payload, self.path.as_deref(), self.field.as_deref())?;
+        debug!("returned versions: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!("No version matches the pattern."));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.first().unwrap().to_string())
+    }
+}
+
+#[test]
+fn test_collect_candidates() {
+    let value: Value = serde_json::from_str(
+        r#"{"data": {"version": "1.2.3", "releases": [
+            {"tag_name": "v1.2.3", "meta": {"version": "1.2.3"}},
+            {"tag_name": "v1.2.2", "meta": {"version": "1.2.2"}}
+        ]}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        collect_candidates(&value, Some("data.version"), None).unwrap(),
+        vec!["1.2.3"]
+    );
+    assert_eq!(
+        collect_candidates(&value, Some("data.releases"), Some("tag_name")).unwrap(),
+        vec!["v1.2.3", "v1.2.2"]
+    );
+    assert_eq!(
+        collect_candidates(&value, Some("data.releases"), Some("meta.version")).unwrap(),
+        vec!["1.2.3", "1.2.2"]
+    );
+    assert_eq!(
+        collect_candidates(&value, Some("data.releases.1.tag_name"), None).unwrap(),
+        vec!["v1.2.2"]
+    );
+    // array of objects without `field`
+    assert!(collect_candidates(&value, Some("data.releases"), None).is_err());
+}
+
+#[test]
+fn test_json_post() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut head = Vec::new();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = len.trim().parse().unwrap();
+            }
+            if line == "\r\n" {
+                break;
+            }
+            head.push(line);
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let payload =
+            r#"{"data": {"project": {"releases": [{"tag": "v1.2.3"}, {"tag": "v1.10.0"}]}}}"#;
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            payload.len(),
+            payload
+        )
+        .unwrap();
+        (head, String::from_utf8(body).unwrap())
+    });
+
+    let mut options = HashMap::new();
+    options.insert(
+        "url".to_string(),
+        format!("http://127.0.0.1:{}/graphql", port),
+    );
+    options.insert("method".to_string(), "post".to_string());
+    options.insert(
+        "body".to_string(),
+        r#"{"query": "{ project { releases { tag } } }"}"#.to_string(),
+    );
+    options.insert("path".to_string(), "data.project.releases".to_string());
+    options.insert("field".to_string(), "tag".to_string());
+    options.insert("pattern".to_string(), r"^v(.+)$".to_string());
+    let checker = JsonChecker::new(&options).unwrap();
+    assert_eq!(checker.check(&Client::new()).unwrap(), "1.10.0");
+    let (head, body) = server.join().unwrap();
+    assert!(head[0].starts_with("POST /graphql "));
+    assert!(head
+        .iter()
+        .any(|h| h.eq_ignore_ascii_case("content-type: application/json\r\n")));
+    assert_eq!(body, r#"{"query": "{ project { releases { tag } } }"}"#);
+
+    // body without POST
+    options.remove("method");
+    assert!(JsonChecker::new(&options).is_err());
+}
+
+#[test]
+fn test_json() {
+    let mut options = HashMap::new();
+    options.insert(
+        "url".to_string(),
+        "https://api.github.com/repos/AOSC-Dev/ciel-rs/releases".to_string(),
+    );
+    options.insert("field".to_string(), "tag_name".to_string());
+    options.insert("pattern".to_string(), r"^v(.+)$".to_string());
+    let client = Client::new();
+    let checker = JsonChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker, VersionOrder};
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://www.kernel.org/releases.json";
+const DEFAULT_MONIKER: &str = "stable";
+
+#[derive(Deserialize)]
+struct KernelRelease {
+    moniker: String,
+    version: String,
+    #[serde(default)]
+    iseol: bool,
+}
+
+#[derive(Deserialize)]
+struct KernelReleases {
+    releases: Vec<KernelRelease>,
+}
+
+pub(crate) struct KernelChecker {
+    moniker: String,
+    pattern: Option<String>,
+    order: VersionOrder,
+}
+
+impl KernelChecker {
+    fn select_version(&self, payload: KernelReleases) -> Result<String> {
+        // there may be several releases with the same moniker (e.g. all the longterm series),
+        // including the last release of a series that reached its end of life
+        let mut versions = payload
+            .releases
+            .into_iter()
+            .filter(|r| r.moniker == self.moniker && !r.iseol)
+            .map(|r| r.version)
+            .collect::<Vec<_>>();
+        debug!("returned {} releases: {:?}", self.moniker, versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!(
+                "kernel.org didn't return any matching {} release!",
+                self.moniker
+            ));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for KernelChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let moniker = config
+            .get("moniker")
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_MONIKER.to_string());
+
+        Ok(KernelChecker {
+            moniker,
+            pattern: pattern_from_config(config)?,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        API_ENDPOINT.to_string()
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(API_ENDPOINT))?;
+        resp.error_for_status_ref()?;
+        let payload: KernelReleases = read_json(resp)?;
+
+        self.select_version(payload)
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let payload = r#"{
+        "latest_stable": {"version": "6.9.3"},
+        "releases": [
+            {"iseol": false, "version": "6.10-rc1", "moniker": "mainline"},
+            {"iseol": false, "version": "6.9.3", "moniker": "stable"},
+            {"iseol": true, "version": "6.8.12", "moniker": "stable"},
+            {"iseol": false, "version": "6.6.32", "moniker": "longterm"},
+            {"iseol": false, "version": "6.1.92", "moniker": "longterm"},
+            {"iseol": false, "version": "5.15.160", "moniker": "longterm"},
+            {"iseol": false, "version": "next-20240531", "moniker": "linux-next"}
+        ]
+    }"#;
+    let mut options = HashMap::new();
+    let checker = KernelChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "6.9.3"
+    );
+    // the end-of-life releases are skipped
+    options.insert("pattern".to_string(), r"^6\.8\.".to_string());
+    let checker = KernelChecker::new(&options).unwrap();
+    assert!(checker
+        .select_version(serde_json::from_str(payload).unwrap())
+        .is_err());
+    options.remove("pattern");
+    options.insert("moniker".to_string(), "longterm".to_string());
+    let checker = KernelChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "6.6.32"
+    );
+    options.insert("moniker".to_string(), "rc".to_string());
+    let checker = KernelChecker::new(&options).unwrap();
+    assert!(checker
+        .select_version(serde_json::from_str(payload).unwrap())
+        .is_err());
+}
+
+#[test]
+fn test_kernel() {
+    let options = HashMap::new();
+    let client = Client::new();
+    let checker = KernelChecker::new(&options).unwrap();
+    let version = checker.check(&client).unwrap();
+    assert!(
+        regex::Regex::new(r"^\d+\.\d+(\.\d+)?$")
+            .unwrap()
+            .is_match(&version),
+        "{}",
+        version
+    );
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://search.maven.org/solrsearch/select";
+
+#[derive(Deserialize)]
+struct MavenDoc {
+    v: String,
+}
+
+#[derive(Deserialize)]
+struct MavenResponse {
+    docs: Vec<MavenDoc>,
+}
+
+#[derive(Deserialize)]
+struct MavenSearch {
+    response: MavenResponse,
+}
+
+pub(crate) struct MavenChecker {
+    group: String,
+    artifact: String,
+    pattern: Option<String>,
+    sort_version: bool,
+    include_prerelease: bool,
+    order: VersionOrder,
+}
+
+impl MavenChecker {
+    fn query(&self) -> String {
+        format!("g:{} AND a:{}", self.group, self.artifact)
+    }
+
+    fn select_version(&self, payload: MavenSearch) -> Result<String> {
+        let unstable = Regex::new(r"(?i)[-.](?:snapshot|alpha|beta)")?;
+        // the versions are returned from the newest to the oldest
+        let mut versions = payload
+            .response
+            .docs
+            .into_iter()
+            .map(|x| x.v)
+            .filter(|v| self.include_prerelease || !unstable.is_match(v))
+            .collect::<Vec<_>>();
+        debug!("returned versions: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!(
+                "Maven Central didn't return any matching versions of {}:{}!",
+                self.group,
+                self.artifact
+            ));
+        }
+        if self.sort_version || self.order.implies_sort() {
+            self.order.sort(&mut versions)?;
+            debug!("after sort: {:?}", versions);
+        }
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for MavenChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let group = must_have!(config, "group", "Group ID")?.to_string();
+        let artifact = must_have!(config, "artifact", "Artifact ID")?.to_string();
+        let sort_version = config
+            .get("sort_version")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        let include_prerelease = config
+            .get("prerelease")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(MavenChecker {
+            group,
+            artifact,
+            pattern: pattern_from_config(config)?,
+            sort_version,
+            include_prerelease,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!(
+            "{}?q=g:{}+AND+a:{}&core=gav&rows=20&wt=json",
+            API_ENDPOINT, self.group, self.artifact
+        )
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(
+            client,
+            client.get(API_ENDPOINT).query(&[
+                ("q", self.query().as_str()),
+                ("core", "gav"),
+                ("rows", "20"),
+                ("wt", "json"),
+            ]),
+        )?;
+        resp.error_for_status_ref()?;
+        let payload: MavenSearch = read_json(resp)?;
+
+        self.select_version(payload)
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let payload = r#"{
+        "responseHeader": {"status": 0},
+        "response": {"numFound": 5, "start": 0, "docs": [
+            {"id": "com.google.guava:guava:34.0.0-SNAPSHOT", "g": "com.google.guava", "a": "guava", "v": "34.0.0-SNAPSHOT"},
+            {"id": "com.google.guava:guava:33.2.1-jre", "g": "com.google.guava", "a": "guava", "v": "33.2.1-jre"},
+            {"id": "com.google.guava:guava:33.2.1-android", "g": "com.google.guava", "a": "guava", "v": "33.2.1-android"},
+            {"id": "com.google.guava:guava:33.10.0-beta-1", "g": "com.google.guava", "a": "guava", "v": "33.10.0-beta-1"},
+            {"id": "com.google.guava:guava:33.2.0-jre", "g": "com.google.guava", "a": "guava", "v": "33.2.0-jre"}
+        ]}
+    }"#;
+    let mut options = HashMap::new();
+    options.insert("group".to_string(), "com.google.guava".to_string());
+    options.insert("artifact".to_string(), "guava".to_string());
+    let checker = MavenChecker::new(&options).unwrap();
+    // snapshots and betas are skipped, and the newest release comes first
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "33.2.1-jre"
+    );
+    options.insert("prerelease".to_string(), "true".to_string());
+    options.insert("sort_version".to_string(), "true".to_string());
+    let checker = MavenChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "34.0.0-SNAPSHOT"
+    );
+}
+
+#[test]
+fn test_maven() {
+    let mut options = HashMap::new();
+    options.insert("group".to_string(), "com.google.guava".to_string());
+    options.insert("artifact".to_string(), "guava".to_string());
+    options.insert("pattern".to_string(), r"^(.+)-jre$".to_string());
+    let client = Client::new();
+    let checker = MavenChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
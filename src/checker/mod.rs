@@ -1,14 +1,55 @@
 use anyhow::{anyhow, Result};
-use regex::Regex;
-use reqwest::blocking::Client;
-use std::{cmp::Ordering, collections::HashMap};
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use percent_encoding::percent_decode_str;
+use regex::{Captures, Regex};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    StatusCode, Url,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    collections::HashMap,
+    io::Read,
+    sync::{Condvar, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
 use version_compare::{compare, Cmp};
 
 mod anitya;
+mod archlinux;
+mod artifactory;
+mod aur;
+mod bitbucket;
+mod changelog;
+mod cpan;
+mod dirlisting;
+mod elpa;
+mod feed;
 mod git;
+mod gitea;
 mod github;
+mod gitiles;
 mod gitlab;
+mod goproxy;
+mod hackage;
+mod hex;
 mod html;
+mod json;
+mod kernel;
+mod maven;
+mod npm;
+mod nuget;
+mod oci;
+mod pypi;
+mod rubygems;
+mod sourcehut;
+mod watch;
 
 #[macro_export]
 macro_rules! must_have {
@@ -24,32 +65,726 @@ macro_rules! use_this {
     };
 }
 
+/// User-Agent sent to the APIs rejecting the requests without one, unless `--user-agent` is set
+const DEFAULT_USER_AGENT: &str = "AOSCFindUpdate/0.1.0";
+
+/// Maximum length of the response body shown in the debug log
+const SNIPPET_LENGTH: usize = 1024;
+
+/// Default maximum size of the pages read by the scraping checkers
+const DEFAULT_MAX_BODY: u64 = 10 * 1024 * 1024;
+
+/// Delay before the first retry, doubled on each subsequent one
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Upper bound of the delay between retries, even if the server asks for a longer one
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Default maximum number of candidates sorted by the checkers
+pub const DEFAULT_MAX_CANDIDATES: usize = 10000;
+
+static REQUEST_OPTIONS: OnceLock<RequestOptions> = OnceLock::new();
+static CANDIDATE_LIMIT: OnceLock<CandidateLimit> = OnceLock::new();
+static DEFAULT_SORT: OnceLock<bool> = OnceLock::new();
+
+thread_local! {
+    /// Number of candidates evaluated by the check running on this thread
+    static CANDIDATES_CONSIDERED: Cell<Option<usize>> = const { Cell::new(None) };
+    /// Template (the `replace` option) assembling the versions from the capture groups
+    static REPLACE_TEMPLATE: RefCell<Option<String>> = const { RefCell::new(None) };
+    /// Whether the pattern being matched combines the alternatives of `patterns`
+    static COMBINED_PATTERNS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Records how many candidates the current check evaluated before picking one
+pub(crate) fn record_candidates(count: usize) {
+    CANDIDATES_CONSIDERED.with(|c| c.set(Some(count)));
+}
+
+/// The `replace` template of the config being checked, if any
+pub(crate) fn replace_template() -> Option<String> {
+    REPLACE_TEMPLATE.with(|t| t.borrow().clone())
+}
+
+/// Options applied to every request sent by the update checkers
+#[derive(Debug, Default)]
+pub struct RequestOptions {
+    /// Reject requests over plain HTTP or FTP
+    pub require_https: bool,
+    /// Hosts allowed to be accessed insecurely even if HTTPS is required
+    pub insecure_hosts: Vec<String>,
+    /// Throttles the requests to a number per second
+    pub rate_limit: Option<RateLimiter>,
+    /// Bounds the number of requests in flight to each host
+    pub host_limit: Option<HostLimiter>,
+    /// How many times a request failing with a transient error is retried
+    pub retries: u32,
+    /// Maximum size in bytes of the pages read by the scraping checkers, 10 MiB if unset
+    pub max_body: Option<u64>,
+    /// User-Agent sent instead of the ones chosen by the checkers
+    pub user_agent: Option<String>,
+    /// Version of Git the `git` checker pretends to be, sent as `git/VERSION`
+    pub git_version: Option<String>,
+}
+
+impl RequestOptions {
+    fn check_url(&self, url: &Url) -> Result<()> {
+        if !self.require_https || url.scheme() == "https" {
+            return Ok(());
+        }
+        if let Some(host) = url.host_str() {
+            if self.insecure_hosts.iter().any(|x| x == host) {
+                return Ok(());
+            }
+        }
+
+        Err(anyhow!(
+            "Refusing to access {} insecurely, HTTPS is required (use --allow-insecure-host to allow this host)",
+            url
+        ))
+    }
+}
+
+/// Spaces out the requests evenly to stay within a requests-per-second budget
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    per_host: bool,
+    /// When the next request may be sent, keyed by host (or an empty string if global)
+    next: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64, per_host: bool) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs_f64(1.0 / rate),
+            per_host,
+            next: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserves the next slot for the host, returning how long to wait for it
+    fn reserve(&self, host: &str, now: Instant) -> Duration {
+        let key = if self.per_host { host } else { "" };
+        let mut next = self.next.lock().unwrap();
+        let slot = next.get(key).map_or(now, |t| (*t).max(now));
+        next.insert(key.to_string(), slot + self.interval);
+
+        slot - now
+    }
+
+    fn wait(&self, url: &Url) {
+        let delay = self.reserve(url.host_str().unwrap_or_default(), Instant::now());
+        if !delay.is_zero() {
+            debug!("rate limited, waiting for {:?}", delay);
+            thread::sleep(delay);
+        }
+    }
+}
+
+/// Bounds the number of concurrent requests to each host, regardless of the number of workers
+#[derive(Debug)]
+pub struct HostLimiter {
+    max: usize,
+    /// Number of requests in flight, keyed by host
+    active: Mutex<HashMap<String, usize>>,
+    released: Condvar,
+}
+
+/// A slot taken from a [`HostLimiter`], given back when dropped
+struct HostPermit<'a> {
+    limiter: &'a HostLimiter,
+    host: String,
+}
+
+impl HostLimiter {
+    pub fn new(max: usize) -> Self {
+        HostLimiter {
+            max,
+            active: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Blocks until fewer than `max` requests to the host are in flight
+    fn acquire(&self, host: &str) -> HostPermit<'_> {
+        let mut active = self.active.lock().unwrap();
+        while active.get(host).copied().unwrap_or(0) >= self.max {
+            debug!("too many requests to {}, waiting", host);
+            active = self.released.wait(active).unwrap();
+        }
+        *active.entry(host.to_string()).or_insert(0) += 1;
+
+        HostPermit {
+            limiter: self,
+            host: host.to_string(),
+        }
+    }
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        let mut active = self.limiter.active.lock().unwrap();
+        if let Some(count) = active.get_mut(&self.host) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&self.host);
+            }
+        }
+        self.limiter.released.notify_all();
+    }
+}
+
+/// Returns the User-Agent set with `--user-agent`, or `default` if there is none
+pub(crate) fn user_agent(default: &'static str) -> &'static str {
+    REQUEST_OPTIONS
+        .get()
+        .and_then(|o| o.user_agent.as_deref())
+        .unwrap_or(default)
+}
+
+/// Returns the Git version set with `--git-version`, or `default` if there is none
+pub(crate) fn git_version(default: &'static str) -> &'static str {
+    REQUEST_OPTIONS
+        .get()
+        .and_then(|o| o.git_version.as_deref())
+        .unwrap_or(default)
+}
+
+/// Sets the options for all requests, which can only be done once
+pub fn set_request_options(options: RequestOptions) {
+    REQUEST_OPTIONS.set(options).ok();
+}
+
+/// What to do when a checker yields more candidates than the limit
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CandidateLimitAction {
+    /// Keep the lexically latest candidates
+    #[default]
+    Truncate,
+    /// Fail the check
+    Error,
+}
+
+/// Limit of the candidates sorted by the checkers, bounding the cost of sorting huge lists
+#[derive(Debug)]
+pub struct CandidateLimit {
+    pub max: usize,
+    pub action: CandidateLimitAction,
+}
+
+impl Default for CandidateLimit {
+    fn default() -> Self {
+        CandidateLimit {
+            max: DEFAULT_MAX_CANDIDATES,
+            action: CandidateLimitAction::default(),
+        }
+    }
+}
+
+impl CandidateLimit {
+    fn apply<S: AsRef<str>>(&self, candidates: &mut Vec<S>) -> Result<()> {
+        if candidates.len() <= self.max {
+            return Ok(());
+        }
+        if self.action == CandidateLimitAction::Error {
+            return Err(anyhow!(
+                "Too many candidates ({}, the limit is {}), please use a more specific pattern",
+                candidates.len(),
+                self.max
+            ));
+        }
+        warn!(
+            "Too many candidates ({}), only the lexically latest {} are considered",
+            candidates.len(),
+            self.max
+        );
+        candidates.sort_unstable_by(|a, b| b.as_ref().cmp(a.as_ref()));
+        candidates.truncate(self.max);
+
+        Ok(())
+    }
+}
+
+/// Sets the candidate limit for all checkers, which can only be done once
+pub fn set_candidate_limit(limit: CandidateLimit) {
+    CANDIDATE_LIMIT.set(limit).ok();
+}
+
+/// Makes `sort_version` default to `true` for all checkers, which can only be done once
+pub fn set_default_sort(enabled: bool) {
+    DEFAULT_SORT.set(enabled).ok();
+}
+
+/// Adds `sort_version=true` to the config if sorting is the default, unless the spec sets it
+fn with_default_sort(
+    config: &HashMap<String, String>,
+    default_sort: bool,
+) -> Cow<'_, HashMap<String, String>> {
+    if !default_sort || config.contains_key("sort_version") {
+        return Cow::Borrowed(config);
+    }
+    let mut config = config.clone();
+    config.insert("sort_version".to_string(), "true".to_string());
+
+    Cow::Owned(config)
+}
+
+/// Whether the response status is likely to go away if the request is retried
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED)
+}
+
+/// Whether the request is worth retrying. A 429 saying that the rate limit is exhausted
+/// (`x-ratelimit-remaining: 0`) won't succeed before the limit resets, so it is returned to the
+/// checker, which may explain it (e.g. the GitHub checker).
+fn should_retry(status: StatusCode, headers: &HeaderMap) -> bool {
+    let exhausted = headers
+        .get("x-ratelimit-remaining")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim() == "0");
+
+    is_transient_status(status) && !exhausted
+}
+
+/// How long to wait before retrying, honoring the `Retry-After` header (in seconds or as an
+/// HTTP date) if the server sent one, and backing off exponentially otherwise
+fn retry_delay(attempt: u32, retry_after: Option<&str>, now: DateTime<Utc>) -> Duration {
+    let requested = retry_after.and_then(|v| {
+        let v = v.trim();
+        if let Ok(seconds) = v.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+        let date = DateTime::parse_from_rfc2822(v).ok()?;
+        Some(
+            (date.with_timezone(&Utc) - now)
+                .to_std()
+                .unwrap_or_default(),
+        )
+    });
+
+    requested
+        .unwrap_or_else(|| RETRY_BASE_DELAY * 2u32.saturating_pow(attempt))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Sends the request built by `builder` using `client`, retrying on connection errors, timeouts,
+/// and 429 or 5xx responses as many times as configured with `--retries`
+pub(crate) fn send(client: &Client, builder: RequestBuilder) -> Result<Response> {
+    let mut request = builder.build()?;
+    debug!("{} {}", request.method(), request.url());
+    let options = REQUEST_OPTIONS.get();
+    if let Some(options) = options {
+        options.check_url(request.url())?;
+    }
+    let retries = options.map_or(0, |o| o.retries);
+    let mut attempt = 1;
+    loop {
+        // requests with a streamed body can't be cloned, and are sent only once
+        let next = if attempt <= retries {
+            request.try_clone()
+        } else {
+            None
+        };
+        if let Some(limiter) = options.and_then(|o| o.rate_limit.as_ref()) {
+            limiter.wait(request.url());
+        }
+        let url = request.url().clone();
+        // held until the response headers arrive, and released while waiting to retry
+        let permit = options
+            .and_then(|o| o.host_limit.as_ref())
+            .map(|limiter| limiter.acquire(url.host_str().unwrap_or_default()));
+        let result = client.execute(request);
+        drop(permit);
+        let retry_after = match result {
+            Ok(resp) => {
+                debug!("response status: {}", resp.status());
+                // without any retry, the caller handles the status as usual
+                if !should_retry(resp.status(), resp.headers()) || attempt == 1 && next.is_none() {
+                    return Ok(resp);
+                }
+                if next.is_none() {
+                    return resp
+                        .error_for_status()
+                        .map_err(|e| gave_up(e.into(), attempt));
+                }
+                resp.headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_string())
+            }
+            Err(e) if (e.is_connect() || e.is_timeout()) && next.is_some() => {
+                debug!("request failed: {}", e);
+                None
+            }
+            Err(e) => return Err(gave_up(e.into(), attempt)),
+        };
+        let delay = retry_delay(attempt - 1, retry_after.as_deref(), Utc::now());
+        warn!(
+            "{} failed (attempt {} of {}), retrying in {:?}",
+            url,
+            attempt,
+            retries + 1,
+            delay
+        );
+        thread::sleep(delay);
+        request = next.ok_or_else(|| anyhow!("Unable to resend the request"))?;
+        attempt += 1;
+    }
+}
+
+/// Mentions how many attempts were made in the error, if the request was retried at all
+fn gave_up(error: anyhow::Error, attempts: u32) -> anyhow::Error {
+    if attempts > 1 {
+        error.context(format!("Gave up after {} attempts", attempts))
+    } else {
+        error
+    }
+}
+
+fn log_body(body: &str) {
+    let end = body
+        .char_indices()
+        .nth(SNIPPET_LENGTH)
+        .map_or(body.len(), |(i, _)| i);
+    debug!("response body: {}", &body[..end]);
+}
+
+/// Reads the response body as text, logging a snippet of it
+pub(crate) fn read_text(resp: Response) -> Result<String> {
+    let body = resp.text()?;
+    log_body(&body);
+
+    Ok(body)
+}
+
+/// Reads at most `limit` bytes, failing as soon as there is more to read
+fn read_bounded<R: Read>(reader: R, limit: u64) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    reader.take(limit + 1).read_to_end(&mut body)?;
+    if body.len() as u64 > limit {
+        return Err(anyhow!("Response body too large (over {} bytes)", limit));
+    }
+
+    Ok(body)
+}
+
+/// Same as `read_text`, but gives up on bodies larger than `--max-body` (whether or not the server
+/// sent a `Content-Length`) instead of buffering them
+pub(crate) fn read_text_bounded(resp: Response) -> Result<String> {
+    let limit = REQUEST_OPTIONS
+        .get()
+        .and_then(|o| o.max_body)
+        .unwrap_or(DEFAULT_MAX_BODY);
+    if let Some(len) = resp.content_length() {
+        if len > limit {
+            return Err(anyhow!("Response body too large ({} bytes)", len));
+        }
+    }
+    let body = String::from_utf8_lossy(&read_bounded(resp, limit)?).into_owned();
+    log_body(&body);
+
+    Ok(body)
+}
+
+/// Reads the response body as JSON, logging a snippet of it
+pub(crate) fn read_json<T: DeserializeOwned>(resp: Response) -> Result<T> {
+    Ok(serde_json::from_str(&read_text(resp)?)?)
+}
+
 pub(crate) fn extract_versions<S: AsRef<str>>(
     pattern: &str,
     collection: &[S],
 ) -> Result<Vec<String>> {
+    Ok(extract_versions_indexed(pattern, collection)?
+        .into_iter()
+        .map(|(_, v)| v)
+        .collect())
+}
+
+/// Same as `extract_versions`, but also returns the index of the item each version came from
+pub(crate) fn extract_versions_indexed<S: AsRef<str>>(
+    pattern: &str,
+    collection: &[S],
+) -> Result<Vec<(usize, String)>> {
+    let template = replace_template();
+    let results = extract_versions_with(pattern, template.as_deref(), collection)?;
+
+    record_candidates(results.len());
+
+    Ok(results)
+}
+
+/// Extracts the versions with the pattern. With a template (e.g. `$1.$2`), the versions are
+/// assembled from the capture groups, otherwise the first matching group (or the whole item if
+/// there is no group) is used
+fn extract_versions_with<S: AsRef<str>>(
+    pattern: &str,
+    template: Option<&str>,
+    collection: &[S],
+) -> Result<Vec<(usize, String)>> {
     let regex = Regex::new(pattern)?;
-    let results = if regex.captures_len() > 1 {
+    let results = if template.is_some() || regex.captures_len() > 1 {
         collection
             .iter()
-            .filter_map(|x| {
-                regex
-                    .captures(x.as_ref())
-                    .and_then(|x| x.get(1))
-                    .map(|x| x.as_str().to_string())
+            .enumerate()
+            .filter_map(|(i, x)| {
+                Some((i, expand_captures(&regex.captures(x.as_ref())?, template)?))
             })
             .collect()
     } else {
         collection
             .iter()
-            .filter(|&x| regex.is_match(x.as_ref()))
-            .map(|x| x.as_ref().to_string())
+            .enumerate()
+            .filter(|(_, x)| regex.is_match(x.as_ref()))
+            .map(|(i, x)| (i, x.as_ref().to_string()))
             .collect()
     };
 
     Ok(results)
 }
 
+/// Same as `extract_versions`, but with every match of the pattern in a text (e.g. a web page)
+/// being a candidate
+pub(crate) fn extract_versions_in_text(pattern: &str, text: &str) -> Result<Vec<String>> {
+    let template = replace_template();
+    let results = extract_versions_in_text_with(pattern, template.as_deref(), text)?;
+
+    record_candidates(results.len());
+
+    Ok(results)
+}
+
+fn extract_versions_in_text_with(
+    pattern: &str,
+    template: Option<&str>,
+    text: &str,
+) -> Result<Vec<String>> {
+    let regex = Regex::new(pattern)?;
+
+    Ok(regex
+        .captures_iter(text)
+        .filter_map(|x| expand_captures(&x, template))
+        .collect())
+}
+
+/// Assembles the version from the captures with the template, or uses group #1 (the whole match
+/// if the pattern has no group)
+fn expand_captures(captures: &Captures, template: Option<&str>) -> Option<String> {
+    if let Some(template) = template {
+        let mut version = String::new();
+        captures.expand(template, &mut version);
+        return Some(version);
+    }
+    if captures.len() == 1 {
+        return Some(captures[0].to_string());
+    }
+    // the alternatives combined from `patterns` each have their own groups, so use the first
+    // group that participated in the match
+    if COMBINED_PATTERNS.with(|c| c.get()) {
+        return captures
+            .iter()
+            .skip(1)
+            .flatten()
+            .next()
+            .map(|x| x.as_str().to_string());
+    }
+    captures.get(1).map(|x| x.as_str().to_string())
+}
+
+/// Strips the `)]}'` prefix that Gerrit and Gitiles put before JSON responses against XSSI
+pub(crate) fn strip_xssi_prefix(body: &str) -> &str {
+    body.trim_start()
+        .strip_prefix(")]}'")
+        .map_or(body, |s| s.trim_start())
+}
+
+/// Collects the targets of the links in an HTML page, as written in the page
+pub(crate) fn collect_hrefs(body: &str) -> Result<Vec<String>> {
+    let href = Regex::new(r#"(?i)<a\s[^>]*href\s*=\s*["']([^"']+)["']"#)?;
+
+    Ok(href
+        .captures_iter(body)
+        .map(|c| c[1].replace("&amp;", "&"))
+        .collect())
+}
+
+/// Collects the names of the entries linked from a directory index (Apache, nginx, Artifactory,
+/// ...). Only the links below `base` are kept, so the sorting links and the links to the parent
+/// directories are ignored.
+pub(crate) fn collect_links(body: &str, base: &Url) -> Result<Vec<String>> {
+    let mut base = base.clone();
+    if !base.path().ends_with('/') {
+        let path = format!("{}/", base.path());
+        base.set_path(&path);
+    }
+
+    Ok(collect_hrefs(body)?
+        .into_iter()
+        .filter_map(|href| {
+            let link = base.join(&href).ok()?;
+            if link.origin() != base.origin() {
+                return None;
+            }
+            // subdirectories (e.g. `1.2.3/`) are named without the trailing slash
+            let path = link.path().strip_prefix(base.path())?.trim_end_matches('/');
+            let name = path.rsplit('/').next().unwrap_or(path);
+            if name.is_empty() {
+                return None;
+            }
+            Some(percent_decode_str(name).decode_utf8_lossy().into_owned())
+        })
+        .collect())
+}
+
+/// Converts a glob pattern to an anchored regular expression, capturing the first `*` (and the
+/// `?` or `[...]` right before it, e.g. `[0-9]*`) as the version
+fn glob_to_regex(glob: &str) -> Result<String> {
+    // the regex of each character or wildcard, and whether it is a wildcard
+    let mut parts: Vec<(String, bool)> = vec![];
+    let mut first_star = None;
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        let part = match c {
+            '*' => {
+                first_star.get_or_insert(parts.len());
+                ".*?".to_string()
+            }
+            '?' => ".".to_string(),
+            '[' => {
+                let mut class = String::new();
+                for c in chars.by_ref() {
+                    if c == ']' && !class.is_empty() {
+                        break;
+                    }
+                    class.push(c);
+                }
+                let class = match class.strip_prefix('!') {
+                    Some(negated) => format!("^{}", negated),
+                    None => class,
+                };
+                format!("[{}]", class.replace('\\', "\\\\"))
+            }
+            c => {
+                parts.push((regex::escape(&c.to_string()), false));
+                continue;
+            }
+        };
+        parts.push((part, true));
+    }
+    if let Some(star) = first_star {
+        let start = parts[..star]
+            .iter()
+            .rposition(|(_, wildcard)| !wildcard)
+            .map_or(0, |i| i + 1);
+        if start == star {
+            // a lone `*` must match something to be a version
+            parts[star].0 = ".+?".to_string();
+        }
+        parts[start].0.insert(0, '(');
+        parts[star].0.push(')');
+    }
+    let regex = format!(
+        "^{}$",
+        parts.into_iter().map(|(part, _)| part).collect::<String>()
+    );
+    Regex::new(&regex).map_err(|e| anyhow!("Invalid glob pattern {}: {}", glob, e))?;
+
+    Ok(regex)
+}
+
+/// Converts the pattern to a regular expression according to the `pattern_type` and `anchor` options
+pub(crate) fn build_pattern(config: &HashMap<String, String>, pattern: &str) -> Result<String> {
+    match config.get("pattern_type").map(|s| s.as_str()) {
+        None | Some("regex") => (),
+        // globs are always anchored
+        Some("glob") => return glob_to_regex(pattern),
+        Some(v) => return Err(anyhow!("Unknown pattern type: {}", v)),
+    }
+    match config.get("anchor").map(|s| s.as_str()) {
+        None | Some("false") => Ok(pattern.to_string()),
+        Some("true") | Some("full") => Ok(format!("^(?:{})$", pattern)),
+        Some(v) => Err(anyhow!("Unknown anchor type: {}", v)),
+    }
+}
+
+/// Splits the `|`-separated patterns, leaving the `|` inside groups and classes alone
+fn split_patterns(patterns: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let (mut depth, mut in_class, mut escaped) = (0, false, false);
+    let mut start = 0;
+    for (i, c) in patterns.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class => depth += 1,
+            ')' if !in_class => depth -= 1,
+            '|' if !in_class && depth == 0 => {
+                result.push(&patterns[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    result.push(&patterns[start..]);
+
+    result
+}
+
+/// Reads the optional `pattern` (or `patterns`) option, taking `pattern_type` and `anchor` into account
+pub(crate) fn pattern_from_config(config: &HashMap<String, String>) -> Result<Option<String>> {
+    if let Some(patterns) = config.get("patterns") {
+        if config.contains_key("pattern") {
+            return Err(anyhow!(
+                "Please specify either pattern or patterns, not both!"
+            ));
+        }
+        let patterns = split_patterns(patterns)
+            .into_iter()
+            .map(|p| Ok(format!("(?:{})", build_pattern(config, p)?)))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(Some(patterns.join("|")));
+    }
+    config
+        .get("pattern")
+        .map(|p| build_pattern(config, p))
+        .transpose()
+}
+
+/// Whether the checker should return the commit of the selected tag instead of the tag itself
+pub(crate) fn returns_commit(config: &HashMap<String, String>) -> Result<bool> {
+    match config.get("return").map(|s| s.as_str()) {
+        None | Some("tag") => Ok(false),
+        Some("commit") => Ok(true),
+        Some(v) => Err(anyhow!("Unknown return type: {}", v)),
+    }
+}
+
+/// Maps the versions extracted from the tag names back to the commits of the tags
+pub(crate) fn map_versions_to_commits(
+    pattern: Option<&str>,
+    tags: &[(String, String)],
+) -> Result<HashMap<String, String>> {
+    let names = tags.iter().map(|(name, _)| name).collect::<Vec<_>>();
+    let versions = if let Some(pattern) = pattern {
+        extract_versions_indexed(pattern, &names)?
+    } else {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (i, name.to_string()))
+            .collect()
+    };
+    let mut commits = HashMap::new();
+    for (i, version) in versions {
+        commits.entry(version).or_insert_with(|| tags[i].1.clone());
+    }
+
+    Ok(commits)
+}
+
 #[inline]
 pub(crate) fn version_compare(a: &str, b: &str) -> Ordering {
     if let Ok(ret) = compare(a, b) {
@@ -64,6 +799,103 @@ pub(crate) fn version_compare(a: &str, b: &str) -> Ordering {
     }
 }
 
+/// Consumes a run of digits, without the leading zeros
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+
+    digits.trim_start_matches('0').to_string()
+}
+
+/// Compares the runs of digits by their numeric value and everything else character by character
+fn natural_compare(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (take_digits(&mut a), take_digits(&mut b));
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+/// How the candidate versions are compared when sorting
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum VersionOrder {
+    /// Lenient comparison using `version_compare`
+    #[default]
+    Lenient,
+    /// Semantic versioning precedence, using `version_compare` for versions that are not semver
+    Semver,
+    /// Natural order, comparing the runs of digits as numbers (e.g. `r999` < `r1234`)
+    Natural,
+}
+
+impl VersionOrder {
+    pub(crate) fn from_config(config: &HashMap<String, String>) -> Result<Self> {
+        let semver = match config.get("semver").map(|s| s.as_str()) {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(v) => return Err(anyhow!("Invalid value for semver: {}", v)),
+        };
+        match config.get("sort").map(|s| s.as_str()) {
+            None | Some("version") if semver => Ok(VersionOrder::Semver),
+            None | Some("version") => Ok(VersionOrder::Lenient),
+            Some("natural") if semver => Err(anyhow!("semver can't be used with sort=natural")),
+            Some("natural") => Ok(VersionOrder::Natural),
+            Some(v) => Err(anyhow!("Invalid value for sort: {}", v)),
+        }
+    }
+
+    /// Whether the order was chosen explicitly, which implies `sort_version` for the checkers
+    /// that don't sort by default
+    pub(crate) fn implies_sort(&self) -> bool {
+        *self != VersionOrder::Lenient
+    }
+
+    pub(crate) fn compare(&self, a: &str, b: &str) -> Ordering {
+        if *self == VersionOrder::Natural {
+            return natural_compare(a, b);
+        }
+        if *self == VersionOrder::Semver {
+            let parse = |v: &str| semver::Version::parse(v.strip_prefix('v').unwrap_or(v));
+            if let (Ok(a), Ok(b)) = (parse(a), parse(b)) {
+                // build metadata is ignored
+                return a.cmp_precedence(&b);
+            }
+        }
+
+        version_compare(a, b)
+    }
+
+    /// Sorts the versions from the newest to the oldest, after applying the candidate limit
+    pub(crate) fn sort<S: AsRef<str>>(&self, versions: &mut Vec<S>) -> Result<()> {
+        record_candidates(versions.len());
+        CANDIDATE_LIMIT
+            .get_or_init(CandidateLimit::default)
+            .apply(versions)?;
+        versions.sort_unstable_by(|a, b| self.compare(b.as_ref(), a.as_ref()));
+
+        Ok(())
+    }
+}
+
 /// Abstraction for an update checker
 pub trait UpdateChecker {
     /// Create a new update checker instance with specified options
@@ -72,22 +904,574 @@ pub trait UpdateChecker {
         Self: Sized + UpdateChecker;
     /// Check the update
     fn check(&self, client: &Client) -> Result<String>;
+    /// URL of the primary endpoint accessed by the checker
+    fn endpoint(&self) -> String;
 }
 
-pub fn check_update(config: &HashMap<String, String>, client: &Client) -> Result<String> {
+fn create_checker(config: &HashMap<String, String>) -> Result<Box<dyn UpdateChecker>> {
     let ty = config
         .get("type")
         .ok_or_else(|| anyhow!("Upstream type not specified."))?
         .as_str();
-    let checker: Result<Box<dyn UpdateChecker>> = match ty {
+    match ty {
         "anitya" => Ok(use_this!(anitya::AnityaChecker, config)),
+        "archlinux" => Ok(use_this!(archlinux::ArchLinuxChecker, config)),
+        "artifactory" => Ok(use_this!(artifactory::ArtifactoryChecker, config)),
+        "aur" => Ok(use_this!(aur::AurChecker, config)),
+        "bitbucket" => Ok(use_this!(bitbucket::BitbucketChecker, config)),
+        "changelog" => Ok(use_this!(changelog::ChangelogChecker, config)),
+        "cpan" => Ok(use_this!(cpan::CpanChecker, config)),
+        "dirlisting" => Ok(use_this!(dirlisting::DirListingChecker, config)),
+        "elpa" => Ok(use_this!(elpa::ElpaChecker, config)),
+        "feed" => Ok(use_this!(feed::FeedChecker, config)),
+        "gitea" | "codeberg" => Ok(use_this!(gitea::GiteaChecker, config)),
         "github" => Ok(use_this!(github::GitHubChecker, config)),
+        "gitiles" => Ok(use_this!(gitiles::GitilesChecker, config)),
         "gitlab" => Ok(use_this!(gitlab::GitLabChecker, config)),
+        "goproxy" => Ok(use_this!(goproxy::GoProxyChecker, config)),
+        "hackage" => Ok(use_this!(hackage::HackageChecker, config)),
+        "hex" => Ok(use_this!(hex::HexChecker, config)),
         "git" => Ok(use_this!(git::GitChecker, config)),
         "html" => Ok(use_this!(html::HTMLChecker, config)),
+        "json" => Ok(use_this!(json::JsonChecker, config)),
+        "kernel" => Ok(use_this!(kernel::KernelChecker, config)),
+        "maven" => Ok(use_this!(maven::MavenChecker, config)),
+        "npm" => Ok(use_this!(npm::NpmChecker, config)),
+        "nuget" => Ok(use_this!(nuget::NugetChecker, config)),
+        "oci" => Ok(use_this!(oci::OciChecker, config)),
+        "pypi" => Ok(use_this!(pypi::PyPIChecker, config)),
+        "rubygems" => Ok(use_this!(rubygems::RubyGemsChecker, config)),
+        "sourcehut" => Ok(use_this!(sourcehut::SourcehutChecker, config)),
+        "watch" => Ok(use_this!(watch::WatchChecker, config)),
         _ => Err(anyhow!("Unknown type")),
+    }
+}
+
+/// Version found by an update checker
+#[derive(Clone, Debug)]
+pub struct UpstreamVersion {
+    pub version: String,
+    /// Number of candidate versions the checker evaluated before picking one
+    pub candidates_considered: usize,
+}
+
+pub fn check_update(config: &HashMap<String, String>, client: &Client) -> Result<UpstreamVersion> {
+    CANDIDATES_CONSIDERED.with(|c| c.set(None));
+    let template = config.get("replace");
+    if template.is_some() && !config.contains_key("pattern") && !config.contains_key("patterns") {
+        return Err(anyhow!("Please specify pattern to use with replace!"));
+    }
+    REPLACE_TEMPLATE.with(|t| *t.borrow_mut() = template.cloned());
+    COMBINED_PATTERNS.with(|c| c.set(config.contains_key("patterns")));
+    let config = with_default_sort(config, DEFAULT_SORT.get().copied().unwrap_or(false));
+    let version = create_checker(&config)?.check(client);
+    REPLACE_TEMPLATE.with(|t| t.take());
+    COMBINED_PATTERNS.with(|c| c.take());
+    let version = version?;
+    // checkers that return a single version don't record anything
+    let candidates_considered = CANDIDATES_CONSIDERED.with(|c| c.take()).unwrap_or(1);
+    debug!("{} candidates considered", candidates_considered);
+
+    Ok(UpstreamVersion {
+        version,
+        candidates_considered,
+    })
+}
+
+/// Returns the URL of the primary endpoint accessed by the checker
+pub fn endpoint(config: &HashMap<String, String>) -> Result<String> {
+    Ok(create_checker(config)?.endpoint())
+}
+
+/// Checks whether the URL is reachable with a HEAD request, falling back to GET if unsupported
+pub fn check_url_health(client: &Client, url: &str) -> Result<StatusCode> {
+    let resp = send(client, client.head(url))?;
+    if resp.status() != StatusCode::METHOD_NOT_ALLOWED
+        && resp.status() != StatusCode::NOT_IMPLEMENTED
+    {
+        return Ok(resp.status());
+    }
+    debug!("{} does not support HEAD requests, retrying with GET", url);
+
+    Ok(send(client, client.get(url))?.status())
+}
+
+#[test]
+fn test_map_versions_to_commits() {
+    let tags = vec![
+        ("v1.0".to_string(), "aaaa".to_string()),
+        ("v1.1".to_string(), "bbbb".to_string()),
+        ("nightly".to_string(), "cccc".to_string()),
+    ];
+    let commits = map_versions_to_commits(Some(r"^v(\d+\.\d+)$"), &tags).unwrap();
+    assert_eq!(commits.len(), 2);
+    assert_eq!(commits["1.0"], "aaaa");
+    assert_eq!(commits["1.1"], "bbbb");
+    let commits = map_versions_to_commits(None, &tags).unwrap();
+    assert_eq!(commits["nightly"], "cccc");
+}
+
+#[test]
+fn test_glob_pattern() {
+    let candidates = [
+        "foo-1.2.3.tar.gz",
+        "foo-1.2.4.tar.xz",
+        "foo-doc-1.2.3.tar.gz",
+        "foo-1.2.3.tar.gz.sig",
+    ];
+    let mut config = HashMap::new();
+    config.insert("pattern".to_string(), "foo-*.tar.gz".to_string());
+    config.insert("pattern_type".to_string(), "glob".to_string());
+    let glob = pattern_from_config(&config).unwrap().unwrap();
+    assert_eq!(
+        extract_versions(&glob, &candidates).unwrap(),
+        vec!["1.2.3", "doc-1.2.3"]
+    );
+    // same result as the equivalent regex
+    assert_eq!(
+        extract_versions(&glob, &candidates).unwrap(),
+        extract_versions(r"^foo-(.+?)\.tar\.gz$", &candidates).unwrap()
+    );
+    // unlike an unanchored regex
+    assert_eq!(
+        extract_versions(r"foo-(.+?)\.tar\.gz", &candidates)
+            .unwrap()
+            .len(),
+        3
+    );
+    config.insert("pattern".to_string(), "foo-[0-9]*.tar.?z".to_string());
+    let glob = pattern_from_config(&config).unwrap().unwrap();
+    // the wildcards right before the first `*` are part of the version
+    assert_eq!(
+        extract_versions(&glob, &candidates).unwrap(),
+        vec!["1.2.3", "1.2.4"]
+    );
+    config.insert("pattern".to_string(), "foo-[!d]*.tar.gz".to_string());
+    let glob = pattern_from_config(&config).unwrap().unwrap();
+    assert_eq!(extract_versions(&glob, &candidates).unwrap(), vec!["1.2.3"]);
+    config.insert("pattern".to_string(), "foo-??*.tar.gz".to_string());
+    let glob = pattern_from_config(&config).unwrap().unwrap();
+    assert_eq!(
+        extract_versions(&glob, &candidates).unwrap(),
+        vec!["1.2.3", "doc-1.2.3"]
+    );
+    config.insert("pattern_type".to_string(), "wildcard".to_string());
+    assert!(pattern_from_config(&config).is_err());
+}
+
+#[test]
+fn test_multiple_patterns() {
+    let candidates = ["v1.2", "release-1.3", "nightly-20240101", "v1.4-rc1"];
+    let mut config = HashMap::new();
+    config.insert("pattern".to_string(), r"^v(\d+\.\d+)$".to_string());
+    let pattern = pattern_from_config(&config).unwrap().unwrap();
+    assert_eq!(
+        extract_versions(&pattern, &candidates).unwrap(),
+        vec!["1.2"]
+    );
+    config.insert("pattern".to_string(), r"^release-(\d+\.\d+)$".to_string());
+    let pattern = pattern_from_config(&config).unwrap().unwrap();
+    assert_eq!(
+        extract_versions(&pattern, &candidates).unwrap(),
+        vec!["1.3"]
+    );
+    // both patterns at once
+    assert!(pattern_from_config(&{
+        let mut config = config.clone();
+        config.insert("patterns".to_string(), "a|b".to_string());
+        config
+    })
+    .is_err());
+    config.remove("pattern");
+    config.insert(
+        "patterns".to_string(),
+        r"^v(\d+\.\d+)$|^release-(\d+\.\d+)$".to_string(),
+    );
+    let pattern = pattern_from_config(&config).unwrap().unwrap();
+    // only group #1 is used, unless the pattern comes from `patterns`
+    assert_eq!(
+        extract_versions(&pattern, &candidates).unwrap(),
+        vec!["1.2"]
+    );
+    COMBINED_PATTERNS.with(|c| c.set(true));
+    assert_eq!(
+        extract_versions(&pattern, &candidates).unwrap(),
+        vec!["1.2", "1.3"]
+    );
+    // `|` inside groups belongs to the pattern itself
+    assert_eq!(
+        split_patterns(r"^(v|ver)(\d+)$|^[|](\d+)$|^a\|b$"),
+        vec![r"^(v|ver)(\d+)$", r"^[|](\d+)$", r"^a\|b$"]
+    );
+    config.insert("patterns".to_string(), "v*|release-*".to_string());
+    config.insert("pattern_type".to_string(), "glob".to_string());
+    let pattern = pattern_from_config(&config).unwrap().unwrap();
+    assert_eq!(
+        extract_versions(&pattern, &candidates).unwrap(),
+        vec!["1.2", "1.3", "1.4-rc1"]
+    );
+    COMBINED_PATTERNS.with(|c| c.take());
+}
+
+#[test]
+fn test_semver_order() {
+    let mut versions = vec![
+        "1.0.0-rc.2",
+        "1.0.0",
+        "1.0.0-rc.10",
+        "0.9.0",
+        "1.0.0-alpha",
+        "v1.0.1+build.5",
+    ];
+    versions.sort_unstable_by(|b, a| VersionOrder::Semver.compare(a, b));
+    assert_eq!(
+        versions,
+        vec![
+            "v1.0.1+build.5",
+            "1.0.0",
+            "1.0.0-rc.10",
+            "1.0.0-rc.2",
+            "1.0.0-alpha",
+            "0.9.0"
+        ]
+    );
+    // build metadata does not affect the precedence
+    assert_eq!(
+        VersionOrder::Semver.compare("1.0.0+a", "1.0.0+b"),
+        Ordering::Equal
+    );
+    // not semver
+    assert_eq!(
+        VersionOrder::Semver.compare("1.2", "1.10"),
+        version_compare("1.2", "1.10")
+    );
+    let mut config = HashMap::new();
+    assert_eq!(
+        VersionOrder::from_config(&config).unwrap(),
+        VersionOrder::Lenient
+    );
+    config.insert("semver".to_string(), "true".to_string());
+    assert_eq!(
+        VersionOrder::from_config(&config).unwrap(),
+        VersionOrder::Semver
+    );
+}
+
+#[test]
+fn test_strip_xssi_prefix() {
+    assert_eq!(strip_xssi_prefix(")]}'\n{\"a\": 1}"), "{\"a\": 1}");
+    assert_eq!(strip_xssi_prefix("{\"a\": 1}"), "{\"a\": 1}");
+}
+
+#[test]
+fn test_collect_links() {
+    let base = Url::parse("https://example.com/pub/foo/").unwrap();
+    // Apache mod_autoindex
+    let apache = r#"<html><head><title>Index of /pub/foo</title></head><body>
+<h1>Index of /pub/foo</h1>
+<table>
+<tr><th><a href="?C=N;O=D">Name</a></th><th><a href="?C=M;O=A">Last modified</a></th></tr>
+<tr><td><a href="/pub/">Parent Directory</a></td></tr>
+<tr><td><a href="foo-1.9.tar.gz">foo-1.9.tar.gz</a></td><td>2023-01-01 10:00</td></tr>
+<tr><td><a href="foo-1.10.tar.gz">foo-1.10.tar.gz</a></td><td>2024-01-01 10:00</td></tr>
+<tr><td><a href="2.0/">2.0/</a></td></tr>
+</table>
+<address>Apache/2.4.57 Server at 1.2.3.4 Port 443</address>
+</body></html>"#;
+    assert_eq!(
+        collect_links(apache, &base).unwrap(),
+        vec!["foo-1.9.tar.gz", "foo-1.10.tar.gz", "2.0"]
+    );
+    // nginx autoindex
+    let nginx = r#"<html><head><title>Index of /foo/</title></head><body>
+<h1>Index of /foo/</h1><hr><pre><a href="../">../</a>
+<a href="foo-2.0.tar.xz">foo-2.0.tar.xz</a>                                     01-Jan-2024 10:00     1048576
+<a href='foo-2.1.tar.xz'>foo-2.1.tar.xz</a>                                     01-Feb-2024 10:00     1048576
+</pre><hr></body></html>"#;
+    let entries = collect_links(nginx, &base).unwrap();
+    assert_eq!(entries, vec!["foo-2.0.tar.xz", "foo-2.1.tar.xz"]);
+    // without the trailing slash, the links are still resolved inside the listed directory
+    let entries = collect_links(nginx, &Url::parse("https://example.com/pub/foo").unwrap());
+    assert_eq!(entries.unwrap(), vec!["foo-2.0.tar.xz", "foo-2.1.tar.xz"]);
+    // numbers in the page chrome are not considered
+    assert_eq!(
+        extract_versions(
+            r"(\d+\.\d+(?:\.\d+)?)",
+            &collect_links(apache, &base).unwrap()
+        )
+        .unwrap(),
+        vec!["1.9", "1.10", "2.0"]
+    );
+    // Artifactory and Nexus, the links to other hosts are not entries
+    let nexus = r#"<html><body><a href="../">../</a>
+<a href="foo-1.0.tar.gz">foo-1.0.tar.gz</a>
+<a HREF="https://nexus.example.com/repository/raw/foo/foo-1.1.tar.gz">foo-1.1.tar.gz</a>
+<a href="https://www.sonatype.com/">Sonatype</a></body></html>"#;
+    let base = Url::parse("https://nexus.example.com/repository/raw/foo/").unwrap();
+    assert_eq!(
+        collect_links(nexus, &base).unwrap(),
+        vec!["foo-1.0.tar.gz", "foo-1.1.tar.gz"]
+    );
+}
+
+#[test]
+fn test_anchor_pattern() {
+    let candidates = ["1.2.3", "1.2.3-rc1", "v1.2.3", "1.2"];
+    let mut config = HashMap::new();
+    config.insert("pattern".to_string(), r"\d+\.\d+\.\d+".to_string());
+    let pattern = pattern_from_config(&config).unwrap().unwrap();
+    assert_eq!(
+        extract_versions(&pattern, &candidates).unwrap(),
+        vec!["1.2.3", "1.2.3-rc1", "v1.2.3"]
+    );
+    config.insert("anchor".to_string(), "true".to_string());
+    let pattern = pattern_from_config(&config).unwrap().unwrap();
+    assert_eq!(
+        extract_versions(&pattern, &candidates).unwrap(),
+        vec!["1.2.3"]
+    );
+    // capture groups are kept
+    config.insert("pattern".to_string(), r"v?(\d+\.\d+\.\d+)".to_string());
+    config.insert("anchor".to_string(), "full".to_string());
+    let pattern = pattern_from_config(&config).unwrap().unwrap();
+    assert_eq!(
+        extract_versions(&pattern, &candidates).unwrap(),
+        vec!["1.2.3", "1.2.3"]
+    );
+    config.insert("anchor".to_string(), "start".to_string());
+    assert!(pattern_from_config(&config).is_err());
+}
+
+#[test]
+fn test_require_https() {
+    let options = RequestOptions {
+        require_https: true,
+        insecure_hosts: vec!["mirror.internal".to_string()],
+        ..Default::default()
+    };
+    let check = |url: &str| options.check_url(&Url::parse(url).unwrap());
+    assert!(check("https://example.com/foo").is_ok());
+    assert!(check("http://example.com/foo").is_err());
+    assert!(check("ftp://example.com/foo").is_err());
+    assert!(check("http://mirror.internal/foo").is_ok());
+    assert!(RequestOptions::default()
+        .check_url(&Url::parse("http://example.com/foo").unwrap())
+        .is_ok());
+}
+
+#[test]
+fn test_candidate_limit() {
+    let mut limit = CandidateLimit {
+        max: 3,
+        action: CandidateLimitAction::Truncate,
+    };
+    let mut candidates = vec!["1.0", "1.1", "1.2"];
+    limit.apply(&mut candidates).unwrap();
+    assert_eq!(candidates, vec!["1.0", "1.1", "1.2"]);
+    let mut candidates = vec!["1.10", "1.9", "2.0", "1.0", "0.9"];
+    limit.apply(&mut candidates).unwrap();
+    assert_eq!(candidates, vec!["2.0", "1.9", "1.10"]);
+    limit.action = CandidateLimitAction::Error;
+    let mut candidates = vec!["1.10", "1.9", "2.0", "1.0", "0.9"];
+    assert!(limit.apply(&mut candidates).is_err());
+    assert_eq!(candidates.len(), 5);
+}
+
+#[test]
+fn test_candidates_considered() {
+    let take = || CANDIDATES_CONSIDERED.with(|c| c.take());
+    take();
+    let mut versions = extract_versions(r"^v(\d+\.\d+)$", &["v1.0", "v1.1", "nightly"]).unwrap();
+    assert_eq!(take(), Some(2));
+    versions.push("1.2".to_string());
+    VersionOrder::Lenient.sort(&mut versions).unwrap();
+    assert_eq!(take(), Some(3));
+    assert_eq!(take(), None);
+}
+
+#[test]
+fn test_rate_limiter() {
+    let now = Instant::now();
+    let limiter = RateLimiter::new(4.0, false);
+    let delays = (0..4)
+        .map(|_| limiter.reserve("example.com", now))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        delays,
+        [0, 250, 500, 750].map(Duration::from_millis).to_vec()
+    );
+    // the budget is shared by all hosts
+    assert_eq!(
+        limiter.reserve("example.org", now),
+        Duration::from_millis(1000)
+    );
+    // no waiting once the slots have passed
+    assert!(limiter
+        .reserve("example.com", now + Duration::from_secs(5))
+        .is_zero());
+    let limiter = RateLimiter::new(2.0, true);
+    assert!(limiter.reserve("example.com", now).is_zero());
+    assert!(limiter.reserve("example.org", now).is_zero());
+    assert_eq!(
+        limiter.reserve("example.com", now),
+        Duration::from_millis(500)
+    );
+}
+
+#[test]
+fn test_host_limiter() {
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use std::sync::Arc;
+
+    let limiter = Arc::new(HostLimiter::new(1));
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let workers = (0..4)
+        .map(|_| {
+            let (limiter, running, peak) = (limiter.clone(), running.clone(), peak.clone());
+            thread::spawn(move || {
+                let _permit = limiter.acquire("github.com");
+                let now = running.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+                peak.fetch_max(now, AtomicOrdering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, AtomicOrdering::SeqCst);
+            })
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    // the calls to the same host were serialized
+    assert_eq!(peak.load(AtomicOrdering::SeqCst), 1);
+    assert!(limiter.active.lock().unwrap().is_empty());
+    // other hosts are not blocked
+    let _github = limiter.acquire("github.com");
+    let _gitlab = limiter.acquire("gitlab.com");
+}
+
+#[test]
+fn test_extract_versions_replace() {
+    let tags = ["v1_2", "v1_10", "release-2.0", "nightly"];
+    // single group
+    assert_eq!(
+        extract_versions_with(r"^v(\d+)_\d+$", None, &tags).unwrap(),
+        vec![(0, "1".to_string()), (1, "1".to_string())]
+    );
+    // multiple groups assembled with the template
+    assert_eq!(
+        extract_versions_with(r"^v(\d+)_(\d+)$", Some("$1.$2"), &tags).unwrap(),
+        vec![(0, "1.2".to_string()), (1, "1.10".to_string())]
+    );
+    assert_eq!(
+        extract_versions_with(
+            r"^v(?<major>\d+)_(?<minor>\d+)$",
+            Some("${major}.${minor}.0"),
+            &tags
+        )
+        .unwrap(),
+        vec![(0, "1.2.0".to_string()), (1, "1.10.0".to_string())]
+    );
+    // no group, match only
+    assert_eq!(
+        extract_versions_with(r"^release-", None, &tags).unwrap(),
+        vec![(2, "release-2.0".to_string())]
+    );
+    assert_eq!(
+        extract_versions_with(r"\d+\.\d+", Some("$0"), &tags).unwrap(),
+        vec![(2, "2.0".to_string())]
+    );
+    // every match in a text
+    let page = r#"<a href="foo-1_2.tar.gz">foo-1_2.tar.gz</a> <a href="foo-1_10.tar.gz">"#;
+    assert_eq!(
+        extract_versions_in_text_with(r"foo-(\d+)_(\d+)\.tar", Some("$1.$2"), page).unwrap(),
+        vec!["1.2", "1.2", "1.10"]
+    );
+    assert_eq!(
+        extract_versions_in_text_with(r">foo-(\d+_\d+)\.tar", None, page).unwrap(),
+        vec!["1_2"]
+    );
+    assert_eq!(
+        extract_versions_in_text_with(r"\d+_\d+", None, page).unwrap(),
+        vec!["1_2", "1_2", "1_10"]
+    );
+}
+
+#[test]
+fn test_retry_delay() {
+    let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+        .unwrap()
+        .with_timezone(&Utc);
+    assert_eq!(retry_delay(0, None, now), Duration::from_secs(1));
+    assert_eq!(retry_delay(2, None, now), Duration::from_secs(4));
+    assert_eq!(retry_delay(30, None, now), MAX_RETRY_DELAY);
+    assert_eq!(retry_delay(0, Some("5"), now), Duration::from_secs(5));
+    assert_eq!(retry_delay(0, Some("3600"), now), MAX_RETRY_DELAY);
+    assert_eq!(
+        retry_delay(0, Some("Wed, 21 Oct 2015 07:28:10 GMT"), now),
+        Duration::from_secs(10)
+    );
+    // a date in the past means no wait at all
+    assert_eq!(
+        retry_delay(0, Some("Wed, 21 Oct 2015 07:27:00 GMT"), now),
+        Duration::ZERO
+    );
+    // unparsable values fall back to the exponential backoff
+    assert_eq!(retry_delay(1, Some("soon"), now), Duration::from_secs(2));
+    assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+    assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+    assert!(!is_transient_status(StatusCode::NOT_IMPLEMENTED));
+    assert!(!is_transient_status(StatusCode::NOT_FOUND));
+    let mut headers = HeaderMap::new();
+    assert!(should_retry(StatusCode::TOO_MANY_REQUESTS, &headers));
+    headers.insert("x-ratelimit-remaining", "12".parse().unwrap());
+    assert!(should_retry(StatusCode::TOO_MANY_REQUESTS, &headers));
+    // retrying before the rate limit resets is pointless
+    headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+    assert!(!should_retry(StatusCode::TOO_MANY_REQUESTS, &headers));
+    assert!(!should_retry(StatusCode::NOT_FOUND, &HeaderMap::new()));
+}
+
+#[test]
+fn test_read_bounded() {
+    let body = b"<html>foo-1.0.tar.gz</html>";
+    assert_eq!(
+        read_bounded(&body[..], body.len() as u64).unwrap(),
+        body.to_vec()
+    );
+    let err = read_bounded(&body[..], 10).unwrap_err();
+    assert!(err.to_string().contains("too large"));
+    // an endless body is not buffered past the limit
+    assert!(read_bounded(std::io::repeat(b'a'), 1024).is_err());
+}
+
+#[test]
+fn test_natural_order() {
+    let config = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
     };
-    let checker = checker?;
+    let order = VersionOrder::from_config(&config(&[("sort", "natural")])).unwrap();
+    assert_eq!(order, VersionOrder::Natural);
+    assert!(order.implies_sort());
+    let mut versions = vec!["r999", "r1234", "r1000", "r0999a"];
+    versions.sort_unstable_by(|a, b| order.compare(b, a));
+    assert_eq!(versions, vec!["r1234", "r1000", "r0999a", "r999"]);
+    assert_eq!(natural_compare("foo-2.10", "foo-2.9"), Ordering::Greater);
+    assert_eq!(natural_compare("1.02", "1.2"), Ordering::Equal);
+    assert!(!VersionOrder::from_config(&config(&[]))
+        .unwrap()
+        .implies_sort());
+    assert!(
+        VersionOrder::from_config(&config(&[("sort", "natural"), ("semver", "true")])).is_err()
+    );
+    assert!(VersionOrder::from_config(&config(&[("sort", "random")])).is_err());
 
-    checker.check(client)
+    // `--default-sort` turns sorting on unless the spec opts out
+    let explicit = config(&[("type", "github"), ("sort_version", "false")]);
+    assert_eq!(with_default_sort(&explicit, true)["sort_version"], "false");
+    let implicit = config(&[("type", "github")]);
+    assert_eq!(with_default_sort(&implicit, true)["sort_version"], "true");
+    assert!(!with_default_sort(&implicit, false).contains_key("sort_version"));
 }
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT, AUTHORIZATION};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://registry.npmjs.org";
+
+#[derive(Deserialize)]
+struct NpmData {
+    #[serde(rename = "dist-tags")]
+    dist_tags: HashMap<String, String>,
+    #[serde(default)]
+    versions: HashMap<String, IgnoredAny>,
+}
+
+pub(crate) struct NpmChecker {
+    registry: String,
+    /// Whether `registry` is configured, only then is `NPM_TOKEN` sent
+    private: bool,
+    name: String,
+    pattern: Option<String>,
+    sort_version: bool,
+    order: VersionOrder,
+}
+
+impl NpmChecker {
+    fn package_url(&self) -> String {
+        // scoped packages (`@scope/name`) need the slash to be escaped
+        format!(
+            "{}/{}",
+            self.registry.trim_end_matches('/'),
+            self.name.replace('/', "%2f")
+        )
+    }
+
+    fn token(&self) -> Option<String> {
+        // never hand the token to the public registry
+        if !self.private {
+            return None;
+        }
+        std::env::var("NPM_TOKEN").ok()
+    }
+
+    fn select_version(&self, payload: NpmData) -> Result<String> {
+        if self.pattern.is_none() && !self.sort_version && !self.order.implies_sort() {
+            return payload
+                .dist_tags
+                .get("latest")
+                .cloned()
+                .ok_or_else(|| anyhow!("npm registry didn't return the latest version!"));
+        }
+        let mut versions = payload.versions.into_keys().collect::<Vec<_>>();
+        debug!("returned versions: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!("npm registry didn't return any matching versions!"));
+        }
+        // the registry returns the versions as an object, so the order is not preserved
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.first().unwrap().to_string())
+    }
+}
+
+impl UpdateChecker for NpmChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let name = must_have!(config, "name", "Package name")?.to_string();
+        let private = config.contains_key("registry");
+        let registry = config
+            .get("registry")
+            .cloned()
+            .unwrap_or_else(|| API_ENDPOINT.to_string());
+        let pattern = pattern_from_config(config)?;
+        let sort_version = config
+            .get("sort_version")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(NpmChecker {
+            registry,
+            private,
+            name,
+            pattern,
+            sort_version,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        self.package_url()
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let mut builder = client
+            .get(self.package_url())
+            .header(ACCEPT, "application/vnd.npm.install-v1+json");
+        if let Some(token) = self.token() {
+            builder = builder.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let resp = send(client, builder)?;
+        resp.error_for_status_ref()?;
+        let payload: NpmData = read_json(resp)?;
+
+        self.select_version(payload)
+    }
+}
+
+#[test]
+fn test_npm_url() {
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "@babel/core".to_string());
+    let checker = NpmChecker::new(&options).unwrap();
+    assert_eq!(
+        checker.package_url(),
+        "https://registry.npmjs.org/@babel%2fcore"
+    );
+    options.insert(
+        "registry".to_string(),
+        "https://npm.example.com/repository/npm/".to_string(),
+    );
+    let checker = NpmChecker::new(&options).unwrap();
+    assert_eq!(
+        checker.package_url(),
+        "https://npm.example.com/repository/npm/@babel%2fcore"
+    );
+}
+
+#[test]
+fn test_npm_registry() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut head = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            head.push(line);
+        }
+        let payload = r#"{"dist-tags": {"latest": "7.24.0"}, "versions": {"7.24.0": {}}}"#;
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            payload.len(),
+            payload
+        )
+        .unwrap();
+        head
+    });
+
+    std::env::set_var("NPM_TOKEN", "secret");
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "@babel/core".to_string());
+    // the token is only sent to a configured registry
+    assert!(NpmChecker::new(&options).unwrap().token().is_none());
+    options.insert(
+        "registry".to_string(),
+        format!("http://127.0.0.1:{}/", port),
+    );
+    let checker = NpmChecker::new(&options).unwrap();
+    assert_eq!(checker.check(&Client::new()).unwrap(), "7.24.0");
+    let head = server.join().unwrap();
+    assert!(head[0].starts_with("GET /@babel%2fcore "));
+    assert!(head
+        .iter()
+        .any(|h| h.eq_ignore_ascii_case("authorization: Bearer secret\r\n")));
+}
+
+#[test]
+fn test_select_version() {
+    let payload = r#"{
+        "dist-tags": {"latest": "4.9.5", "next": "5.0.0-beta"},
+        "versions": {"4.9.5": {}, "5.0.0-beta": {}, "3.9.10": {}, "4.10.0": {}}
+    }"#;
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "typescript".to_string());
+    let checker = NpmChecker::new(&options).unwrap();
+    // the `latest` dist-tag is used unless the versions are sorted or filtered
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "4.9.5"
+    );
+    options.insert("sort_version".to_string(), "true".to_string());
+    let checker = NpmChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "5.0.0-beta"
+    );
+    options.insert("pattern".to_string(), r"^\d+\.\d+\.\d+$".to_string());
+    let checker = NpmChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "4.10.0"
+    );
+}
+
+#[test]
+fn test_npm() {
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "typescript".to_string());
+    let client = Client::new();
+    let checker = NpmChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
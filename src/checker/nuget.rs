@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://api.nuget.org/v3-flatcontainer";
+
+#[derive(Deserialize)]
+struct NugetIndex {
+    /// All the versions of the package, from the oldest to the newest
+    versions: Vec<String>,
+}
+
+pub(crate) struct NugetChecker {
+    id: String,
+    pattern: Option<String>,
+    include_prerelease: bool,
+    order: VersionOrder,
+}
+
+impl NugetChecker {
+    fn select_version(&self, payload: NugetIndex) -> Result<String> {
+        let mut versions = payload
+            .versions
+            .into_iter()
+            .filter(|v| self.include_prerelease || !v.contains('-'))
+            .collect::<Vec<_>>();
+        debug!("returned versions: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!(
+                "NuGet didn't return any matching versions of {}!",
+                self.id
+            ));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for NugetChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let id = must_have!(config, "id", "Package ID")?.to_string();
+        let include_prerelease = config
+            .get("prerelease")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(NugetChecker {
+            id,
+            pattern: pattern_from_config(config)?,
+            include_prerelease,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        // package IDs are case-insensitive, but the flat container only serves lowercase paths
+        format!("{}/{}/index.json", API_ENDPOINT, self.id.to_lowercase())
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(self.endpoint()))?;
+        resp.error_for_status_ref()?;
+        let payload: NugetIndex = read_json(resp)?;
+
+        self.select_version(payload)
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let payload = r#"{"versions": ["12.0.3", "13.0.1", "13.0.2-beta1", "13.0.3", "14.0.1-beta1"]}"#;
+    let mut options = HashMap::new();
+    options.insert("id".to_string(), "Newtonsoft.Json".to_string());
+    let checker = NugetChecker::new(&options).unwrap();
+    assert!(checker.endpoint().ends_with("/newtonsoft.json/index.json"));
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "13.0.3"
+    );
+    options.insert("prerelease".to_string(), "true".to_string());
+    let checker = NugetChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "14.0.1-beta1"
+    );
+    assert!(checker
+        .select_version(serde_json::from_str(r#"{"versions": []}"#).unwrap())
+        .is_err());
+}
+
+#[test]
+fn test_nuget() {
+    let mut options = HashMap::new();
+    options.insert("id".to_string(), "Newtonsoft.Json".to_string());
+    let client = Client::new();
+    let checker = NugetChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{AUTHORIZATION, LINK, WWW_AUTHENTICATE};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+const DOCKER_HUB_REGISTRY: &str = "registry-1.docker.io";
+
+#[derive(Deserialize)]
+struct OciTagList {
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct OciToken {
+    token: Option<String>,
+    access_token: Option<String>,
+}
+
+pub(crate) struct OciChecker {
+    registry: String,
+    repository: String,
+    pattern: Option<String>,
+    order: VersionOrder,
+}
+
+/// Splits the image reference into the registry host and the repository name
+fn parse_image(image: &str) -> (String, String) {
+    let (first, rest) = match image.split_once('/') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (image, None),
+    };
+    match rest {
+        Some(rest) if first.contains('.') || first.contains(':') || first == "localhost" => {
+            let registry = if first == "docker.io" {
+                DOCKER_HUB_REGISTRY
+            } else {
+                first
+            };
+            let repository = if registry == DOCKER_HUB_REGISTRY && !rest.contains('/') {
+                format!("library/{}", rest)
+            } else {
+                rest.to_string()
+            };
+            (registry.to_string(), repository)
+        }
+        Some(_) => (DOCKER_HUB_REGISTRY.to_string(), image.to_string()),
+        // official images on Docker Hub
+        None => (
+            DOCKER_HUB_REGISTRY.to_string(),
+            format!("library/{}", image),
+        ),
+    }
+}
+
+/// Parses the parameters of a `Bearer` authentication challenge
+fn parse_challenge(header: &str) -> Result<HashMap<String, String>> {
+    let (scheme, params) = header
+        .trim()
+        .split_once(' ')
+        .ok_or_else(|| anyhow!("Invalid authentication challenge: {}", header))?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return Err(anyhow!("Unsupported authentication scheme: {}", scheme));
+    }
+    let regex = Regex::new(r#"(\w+)="([^"]*)""#)?;
+
+    Ok(regex
+        .captures_iter(params)
+        .map(|c| (c[1].to_string(), c[2].to_string()))
+        .collect())
+}
+
+/// Extracts the URL of the next page from the `Link` header
+fn next_link(resp: &Response) -> Option<String> {
+    let link = resp.headers().get(LINK)?.to_str().ok()?;
+    let (url, rel) = link.split_once(';')?;
+    if !rel.contains("rel=\"next\"") {
+        return None;
+    }
+
+    Some(
+        url.trim()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .to_string(),
+    )
+}
+
+impl OciChecker {
+    fn request_token(&self, client: &Client, challenge: &str) -> Result<String> {
+        let params = parse_challenge(challenge)?;
+        let realm = params
+            .get("realm")
+            .ok_or_else(|| anyhow!("Authentication challenge has no realm"))?;
+        let query = params
+            .iter()
+            .filter(|(k, _)| k.as_str() != "realm")
+            .collect::<Vec<_>>();
+        let resp = send(client, client.get(realm).query(&query))?;
+        resp.error_for_status_ref()?;
+        let payload: OciToken = read_json(resp)?;
+
+        payload
+            .token
+            .or(payload.access_token)
+            .ok_or_else(|| anyhow!("Registry didn't return an access token!"))
+    }
+
+    fn list_tags(&self, client: &Client) -> Result<Vec<String>> {
+        let base = format!("https://{}", self.registry);
+        let mut url = format!("{}/v2/{}/tags/list", base, self.repository);
+        let mut token: Option<String> = None;
+        let mut tags = Vec::new();
+        loop {
+            let mut builder = client.get(&url);
+            if let Some(token) = &token {
+                builder = builder.header(AUTHORIZATION, format!("Bearer {}", token));
+            }
+            let resp = send(client, builder)?;
+            if resp.status() == StatusCode::UNAUTHORIZED && token.is_none() {
+                let challenge = resp
+                    .headers()
+                    .get(WWW_AUTHENTICATE)
+                    .ok_or_else(|| anyhow!("Registry requires authentication"))?
+                    .to_str()?;
+                token = Some(self.request_token(client, challenge)?);
+                continue;
+            }
+            resp.error_for_status_ref()?;
+            let next = next_link(&resp);
+            let payload: OciTagList = read_json(resp)?;
+            tags.extend(payload.tags.unwrap_or_default());
+            match next {
+                Some(next) if next.starts_with('/') => url = format!("{}{}", base, next),
+                Some(next) => url = next,
+                None => break,
+            }
+        }
+
+        Ok(tags)
+    }
+}
+
+impl UpdateChecker for OciChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let image = must_have!(config, "image", "Container image name")?;
+        let (registry, repository) = parse_image(image);
+        let pattern = pattern_from_config(config)?;
+
+        Ok(OciChecker {
+            registry,
+            repository,
+            pattern,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!("https://{}/v2/{}/tags/list", self.registry, self.repository)
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let mut tags = self.list_tags(client)?;
+        debug!("returned tags: {:?}", tags);
+        if let Some(pattern) = &self.pattern {
+            tags = extract_versions(pattern, &tags)?;
+        }
+        debug!("after filter: {:?}", tags);
+        if tags.is_empty() {
+            return Err(anyhow!(
+                "Registry ({}) didn't return any tags!",
+                self.registry
+            ));
+        }
+        self.order.sort(&mut tags)?;
+
+        Ok(tags.first().unwrap().to_string())
+    }
+}
+
+#[test]
+fn test_parse_image() {
+    assert_eq!(
+        parse_image("ghcr.io/owner/name"),
+        ("ghcr.io".to_string(), "owner/name".to_string())
+    );
+    assert_eq!(
+        parse_image("alpine"),
+        (
+            DOCKER_HUB_REGISTRY.to_string(),
+            "library/alpine".to_string()
+        )
+    );
+    assert_eq!(
+        parse_image("docker.io/alpine"),
+        (
+            DOCKER_HUB_REGISTRY.to_string(),
+            "library/alpine".to_string()
+        )
+    );
+    assert_eq!(
+        parse_image("grafana/grafana"),
+        (
+            DOCKER_HUB_REGISTRY.to_string(),
+            "grafana/grafana".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_parse_challenge() {
+    let params = parse_challenge(
+        r#"Bearer realm="https://ghcr.io/token",service="ghcr.io",scope="repository:owner/name:pull,push""#,
+    )
+    .unwrap();
+    assert_eq!(params["realm"], "https://ghcr.io/token");
+    assert_eq!(params["service"], "ghcr.io");
+    assert_eq!(params["scope"], "repository:owner/name:pull,push");
+    assert!(parse_challenge(r#"Basic realm="registry""#).is_err());
+}
+
+#[test]
+fn test_oci() {
+    let mut options = HashMap::new();
+    options.insert("image".to_string(), "alpine".to_string());
+    options.insert("pattern".to_string(), r"^\d+\.\d+\.\d+$".to_string());
+    let client = Client::new();
+    let checker = OciChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://pypi.org/pypi";
+
+#[derive(Deserialize)]
+struct PyPIInfo {
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct PyPIFile {
+    #[serde(default)]
+    yanked: bool,
+}
+
+#[derive(Deserialize)]
+struct PyPIData {
+    info: PyPIInfo,
+    #[serde(default)]
+    releases: HashMap<String, Vec<PyPIFile>>,
+}
+
+/// Whether the version is a pre-release (alpha, beta, release candidate) or a development release
+fn is_prerelease(version: &str) -> bool {
+    let pattern = Regex::new(r"(?i)\d[-_.]?(?:a|b|c|rc|alpha|beta|pre|preview)\d*|dev").unwrap();
+    pattern.is_match(version)
+}
+
+pub(crate) struct PyPIChecker {
+    name: String,
+    pattern: Option<String>,
+    stable_only: bool,
+    order: VersionOrder,
+}
+
+impl PyPIChecker {
+    fn select_version(&self, payload: PyPIData) -> Result<String> {
+        if self.pattern.is_none() && !self.stable_only {
+            return Ok(payload.info.version);
+        }
+        // skip the releases without any files or with all of them yanked
+        let mut versions = payload
+            .releases
+            .into_iter()
+            .filter(|(_, files)| files.iter().any(|f| !f.yanked))
+            .map(|(version, _)| version)
+            .filter(|version| !self.stable_only || !is_prerelease(version))
+            .collect::<Vec<_>>();
+        debug!("returned releases: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!("PyPI didn't return any matching releases!"));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.first().unwrap().to_string())
+    }
+}
+
+impl UpdateChecker for PyPIChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let name = must_have!(config, "name", "Project name")?.to_string();
+        let pattern = pattern_from_config(config)?;
+        let stable_only = config
+            .get("stable_only")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(PyPIChecker {
+            name,
+            pattern,
+            stable_only,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/{}/json", API_ENDPOINT, self.name)
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(self.endpoint()))?;
+        resp.error_for_status_ref()?;
+        let payload: PyPIData = read_json(resp)?;
+
+        self.select_version(payload)
+    }
+}
+
+#[test]
+fn test_is_prerelease() {
+    for version in [
+        "1.0a1",
+        "1.0b2",
+        "1.0rc1",
+        "1.0.0-rc.1",
+        "2.0.dev3",
+        "3.0c1",
+        "1.0alpha",
+    ] {
+        assert!(is_prerelease(version), "{}", version);
+    }
+    for version in ["1.0", "2.31.0", "1.0.post1", "2024.1.1", "1.0+cpu"] {
+        assert!(!is_prerelease(version), "{}", version);
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let payload = r#"{
+        "info": {"version": "2.0"},
+        "releases": {
+            "1.0": [{"yanked": false}],
+            "2.0": [{"yanked": false}],
+            "2.1": [{"yanked": true}],
+            "2.2": [],
+            "3.0rc1": [{"yanked": false}]
+        }
+    }"#;
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "foo".to_string());
+    let checker = PyPIChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "2.0"
+    );
+    options.insert("pattern".to_string(), r"^\d+\.\d+\w*$".to_string());
+    let checker = PyPIChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "3.0rc1"
+    );
+    options.insert("stable_only".to_string(), "true".to_string());
+    let checker = PyPIChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "2.0"
+    );
+    options.insert("pattern".to_string(), r"^1\.".to_string());
+    let checker = PyPIChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "1.0"
+    );
+}
+
+#[test]
+fn test_pypi() {
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "requests".to_string());
+    options.insert("stable_only".to_string(), "true".to_string());
+    let client = Client::new();
+    let checker = PyPIChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
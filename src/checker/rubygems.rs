@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use super::{extract_versions, pattern_from_config, read_json, send, UpdateChecker, VersionOrder};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use log::debug;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+const API_ENDPOINT: &str = "https://rubygems.org/api/v1/versions/";
+
+#[derive(Deserialize)]
+struct RubyGemsVersion {
+    number: String,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+pub(crate) struct RubyGemsChecker {
+    name: String,
+    pattern: Option<String>,
+    include_prerelease: bool,
+    order: VersionOrder,
+}
+
+impl RubyGemsChecker {
+    fn select_version(&self, payload: Vec<RubyGemsVersion>) -> Result<String> {
+        let mut versions = payload
+            .into_iter()
+            .filter(|v| self.include_prerelease || !v.prerelease)
+            .map(|v| v.number)
+            .collect::<Vec<_>>();
+        debug!("returned versions: {:?}", versions);
+        if let Some(pattern) = &self.pattern {
+            versions = extract_versions(pattern, &versions)?;
+        }
+        debug!("after filter: {:?}", versions);
+        if versions.is_empty() {
+            return Err(anyhow!("RubyGems didn't return any matching versions!"));
+        }
+        self.order.sort(&mut versions)?;
+
+        Ok(versions.swap_remove(0))
+    }
+}
+
+impl UpdateChecker for RubyGemsChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let name = must_have!(config, "name", "Gem name")?.to_string();
+        let pattern = pattern_from_config(config)?;
+        let include_prerelease = config
+            .get("prerelease")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+
+        Ok(RubyGemsChecker {
+            name,
+            pattern,
+            include_prerelease,
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        format!(
+            "{}{}.json",
+            API_ENDPOINT,
+            utf8_percent_encode(&self.name, NON_ALPHANUMERIC)
+        )
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let resp = send(client, client.get(self.endpoint()))?;
+        resp.error_for_status_ref()?;
+        let payload: Vec<RubyGemsVersion> = read_json(resp)?;
+
+        self.select_version(payload)
+    }
+}
+
+#[test]
+fn test_select_version() {
+    let payload = r#"[
+        {"number": "8.0.0.beta1", "prerelease": true},
+        {"number": "7.1.10", "prerelease": false},
+        {"number": "7.2.1", "prerelease": false},
+        {"number": "7.10.0", "prerelease": false},
+        {"number": "6.1.7", "prerelease": false}
+    ]"#;
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "rails".to_string());
+    let checker = RubyGemsChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "7.10.0"
+    );
+    options.insert("prerelease".to_string(), "true".to_string());
+    let checker = RubyGemsChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "8.0.0.beta1"
+    );
+    options.insert("pattern".to_string(), r"^6\.".to_string());
+    let checker = RubyGemsChecker::new(&options).unwrap();
+    assert_eq!(
+        checker
+            .select_version(serde_json::from_str(payload).unwrap())
+            .unwrap(),
+        "6.1.7"
+    );
+}
+
+#[test]
+fn test_rubygems() {
+    let mut options = HashMap::new();
+    options.insert("name".to_string(), "rails".to_string());
+    let client = Client::new();
+    let checker = RubyGemsChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use super::{git::GitChecker, UpdateChecker};
+use crate::must_have;
+use anyhow::{anyhow, Result};
+use reqwest::blocking::Client;
+
+const DEFAULT_INSTANCE: &str = "https://git.sr.ht";
+
+/// Normalizes the repository name to `~user/project`, the `~` being optional in the spec
+fn normalize_repo(repo: &str) -> Result<String> {
+    let name = repo.trim_start_matches('~');
+    match name.split_once('/') {
+        Some((user, project))
+            if !user.is_empty() && !project.is_empty() && !project.contains('/') =>
+        {
+            Ok(format!("~{}", name))
+        }
+        _ => Err(anyhow!(
+            "Invalid repository name {}, expected ~user/project",
+            repo
+        )),
+    }
+}
+
+/// The repositories hosted on Sourcehut are checked with the Git smart HTTP protocol, like the
+/// `git` checker, since the REST API of git.sr.ht has been retired
+pub(crate) struct SourcehutChecker {
+    git: GitChecker,
+}
+
+impl UpdateChecker for SourcehutChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let repo = normalize_repo(must_have!(config, "repo", "Repository name")?)?;
+        let instance = config
+            .get("instance")
+            .map(|s| s.trim_end_matches('/'))
+            .unwrap_or(DEFAULT_INSTANCE);
+        let mut git_config = config.clone();
+        git_config.insert("url".to_string(), format!("{}/{}", instance, repo));
+
+        Ok(SourcehutChecker {
+            git: GitChecker::new(&git_config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        self.git.endpoint()
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        self.git.check(client)
+    }
+}
+
+#[test]
+fn test_normalize_repo() {
+    assert_eq!(
+        normalize_repo("~sircmpwn/scdoc").unwrap(),
+        "~sircmpwn/scdoc"
+    );
+    assert_eq!(normalize_repo("sircmpwn/scdoc").unwrap(), "~sircmpwn/scdoc");
+    assert!(normalize_repo("~sircmpwn").is_err());
+    assert!(normalize_repo("~sircmpwn/scdoc/tree").is_err());
+    assert!(normalize_repo("~/scdoc").is_err());
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "~sircmpwn/scdoc".to_string());
+    let checker = SourcehutChecker::new(&options).unwrap();
+    assert_eq!(
+        checker.endpoint(),
+        "https://git.sr.ht/~sircmpwn/scdoc/info/refs?service=git-upload-pack"
+    );
+    options.insert(
+        "instance".to_string(),
+        "https://git.example.org/".to_string(),
+    );
+    let checker = SourcehutChecker::new(&options).unwrap();
+    assert!(checker
+        .endpoint()
+        .starts_with("https://git.example.org/~sircmpwn/scdoc/"));
+}
+
+#[test]
+fn test_sourcehut() {
+    let mut options = HashMap::new();
+    options.insert("repo".to_string(), "~sircmpwn/scdoc".to_string());
+    options.insert("pattern".to_string(), r"^(\d+\.\d+\.\d+)$".to_string());
+    let client = Client::new();
+    let checker = SourcehutChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+
+use super::{
+    collect_hrefs, extract_versions, extract_versions_in_text, read_text, read_text_bounded,
+    record_candidates, replace_template, send, UpdateChecker, VersionOrder,
+};
+use anyhow::{anyhow, Result};
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::Client;
+
+/// Placeholders substituted in the matching patterns, as defined by uscan(1)
+const SUBSTITUTIONS: &[(&str, &str)] = &[
+    ("@ANY_VERSION@", r"[-_]?v?(\d[\-+\.:\~\da-zA-Z]*)"),
+    (
+        "@ARCHIVE_EXT@",
+        r"(?:\.(?:tar\.xz|tar\.bz2|tar\.gz|tar\.zstd?|zip|tgz|tbz|txz))",
+    ),
+    (
+        "@SIGNATURE_EXT@",
+        r"(?:\.(?:tar\.xz|tar\.bz2|tar\.gz|tar\.zstd?|zip|tgz|tbz|txz))(?:\.(?:asc|pgp|gpg|sig|sign))",
+    ),
+    ("@DEB_EXT@", r"[\+~](debian|dfsg|ds|deb)(\.)?(\d+)?$"),
+];
+
+/// A single rule of the watch file
+#[derive(Debug, PartialEq)]
+struct WatchRule {
+    /// URL of the page listing the releases
+    url: String,
+    pattern: String,
+    options: HashMap<String, String>,
+}
+
+/// Splits the `opts=...` prefix from the watch line, which may be quoted
+fn split_options(line: &str) -> Result<(HashMap<String, String>, &str)> {
+    let Some(rest) = line.strip_prefix("opts=") else {
+        return Ok((HashMap::new(), line));
+    };
+    let (options, rest) = match rest.strip_prefix('"') {
+        Some(rest) => rest
+            .split_once('"')
+            .ok_or_else(|| anyhow!("Unterminated quote in watch options: {}", line))?,
+        None => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+    };
+    let options = options
+        .split(',')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|x| match x.split_once('=') {
+            Some((k, v)) => (k.to_string(), v.to_string()),
+            None => (x.to_string(), String::new()),
+        })
+        .collect();
+
+    Ok((options, rest.trim_start()))
+}
+
+/// Parses one watch line: `[opts=...] <URL> [<pattern> [<version> [<script>]]]`
+fn parse_watch_line(line: &str, package: Option<&str>) -> Result<WatchRule> {
+    let (options, rest) = split_options(line.trim())?;
+    if let Some(mode) = options.get("mode").filter(|m| *m != "LWP") {
+        return Err(anyhow!("Unsupported watch mode: {}", mode));
+    }
+    let mut fields = rest.split_whitespace();
+    let url = fields
+        .next()
+        .ok_or_else(|| anyhow!("Watch line has no URL: {}", line))?;
+    let (url, pattern) = match fields.next() {
+        Some(pattern) => (url.trim_end_matches('/'), pattern),
+        // the pattern is the last component of the URL
+        None => url
+            .rsplit_once('/')
+            .ok_or_else(|| anyhow!("Watch line has no matching pattern: {}", line))?,
+    };
+    if url.contains('(') {
+        return Err(anyhow!(
+            "Patterns in the directory part of watch URLs are not supported: {}",
+            url
+        ));
+    }
+    let mut pattern = pattern.to_string();
+    let mut url = url.to_string();
+    if line.contains("@PACKAGE@") {
+        let package = package.ok_or_else(|| {
+            anyhow!("Please specify the package name for substituting @PACKAGE@!")
+        })?;
+        pattern = pattern.replace("@PACKAGE@", &regex::escape(package));
+        url = url.replace("@PACKAGE@", package);
+    }
+    for (from, to) in SUBSTITUTIONS {
+        pattern = pattern.replace(from, to);
+    }
+
+    Ok(WatchRule {
+        url,
+        pattern,
+        options,
+    })
+}
+
+/// Parses the watch file and returns the rule of the main upstream tarball
+fn parse_watch_file(contents: &str, package: Option<&str>) -> Result<WatchRule> {
+    let joined = contents.replace("\\\n", "");
+    let mut lines = joined
+        .lines()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty() && !x.starts_with('#'));
+    let version = lines
+        .next()
+        .and_then(|x| x.strip_prefix("version="))
+        .ok_or_else(|| anyhow!("Watch file does not start with a version line"))?;
+    if version != "3" && version != "4" {
+        return Err(anyhow!("Unsupported watch file version: {}", version));
+    }
+    let line = lines
+        .next()
+        .ok_or_else(|| anyhow!("Watch file does not contain any rules"))?;
+    // additional lines describe the other components, which are not needed here
+    for ignored in lines {
+        debug!("ignored watch line: {}", ignored);
+    }
+
+    parse_watch_line(line, package)
+}
+
+/// Applies Perl-style `s///` substitutions separated by `;`, like the `uversionmangle` option
+fn apply_mangle(rules: &str, version: &str) -> Result<String> {
+    // Perl uses `$1` and `\1` for the groups, which may be followed by other characters
+    let group = Regex::new(r"[$\\](\d+)")?;
+    let mut version = version.to_string();
+    for rule in rules.split(';').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        let mut chars = rule.chars();
+        if chars.next() != Some('s') {
+            return Err(anyhow!("Unsupported mangle rule: {}", rule));
+        }
+        let delimiter = chars
+            .next()
+            .ok_or_else(|| anyhow!("Invalid mangle rule: {}", rule))?;
+        let mut parts = vec![String::new()];
+        let mut escaped = false;
+        for c in chars {
+            if escaped {
+                if c != delimiter {
+                    parts.last_mut().unwrap().push('\\');
+                }
+                parts.last_mut().unwrap().push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == delimiter {
+                parts.push(String::new());
+            } else {
+                parts.last_mut().unwrap().push(c);
+            }
+        }
+        let [pattern, replacement, flags] = parts.as_slice() else {
+            return Err(anyhow!("Invalid mangle rule: {}", rule));
+        };
+        let pattern = if flags.contains('i') {
+            Regex::new(&format!("(?i){}", pattern))?
+        } else {
+            Regex::new(pattern)?
+        };
+        let replacement = group.replace_all(replacement, "$${$1}");
+        version = if flags.contains('g') {
+            pattern.replace_all(&version, replacement.as_ref())
+        } else {
+            pattern.replace(&version, replacement.as_ref())
+        }
+        .to_string();
+    }
+
+    Ok(version)
+}
+
+/// Finds the versions in the links of the page, or in the whole page in plain search mode
+fn find_versions(rule: &WatchRule, body: &str) -> Result<Vec<String>> {
+    let plain = rule.options.get("searchmode").map(|x| x.as_str()) == Some("plain");
+    let pattern = if plain {
+        rule.pattern.clone()
+    } else {
+        format!("^(?:.*/)?{}$", rule.pattern)
+    };
+    // the links are matched as written, since watch files may point outside of the page
+    let links = if plain { vec![] } else { collect_hrefs(body)? };
+    if replace_template().is_some() {
+        return if plain {
+            extract_versions_in_text(&pattern, body)
+        } else {
+            extract_versions(&pattern, &links)
+        };
+    }
+    let join = |c: regex::Captures| {
+        // uscan joins multiple groups with dots
+        c.iter()
+            .skip(1)
+            .flatten()
+            .map(|x| x.as_str())
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+    let pattern = Regex::new(&pattern)?;
+    let versions = if plain {
+        pattern.captures_iter(body).map(join).collect::<Vec<_>>()
+    } else {
+        links
+            .iter()
+            .filter_map(|x| pattern.captures(x).map(join))
+            .collect()
+    };
+    record_candidates(versions.len());
+
+    Ok(versions)
+}
+
+pub(crate) struct WatchChecker {
+    /// URL of the watch file
+    url: Option<String>,
+    /// Inline watch line
+    watch: Option<String>,
+    package: Option<String>,
+    order: VersionOrder,
+}
+
+impl WatchChecker {
+    fn rule(&self, client: &Client) -> Result<WatchRule> {
+        match (&self.url, &self.watch) {
+            (Some(url), _) => {
+                let resp = send(client, client.get(url))?;
+                resp.error_for_status_ref()?;
+                parse_watch_file(&read_text(resp)?, self.package.as_deref())
+            }
+            (None, Some(watch)) => parse_watch_line(watch, self.package.as_deref()),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+impl UpdateChecker for WatchChecker {
+    fn new(config: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized + UpdateChecker,
+    {
+        let url = config.get("url").cloned();
+        let watch = config.get("watch").cloned();
+        if url.is_none() && watch.is_none() {
+            return Err(anyhow!(
+                "Please specify the URL of the watch file or an inline watch line!"
+            ));
+        }
+
+        Ok(WatchChecker {
+            url,
+            watch,
+            package: config.get("package").cloned(),
+            order: VersionOrder::from_config(config)?,
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        match (&self.url, &self.watch) {
+            (Some(url), _) => url.clone(),
+            (None, Some(watch)) => parse_watch_line(watch, self.package.as_deref())
+                .map(|rule| rule.url)
+                .unwrap_or_default(),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    fn check(&self, client: &Client) -> Result<String> {
+        let rule = self.rule(client)?;
+        debug!("watch rule: {:?}", rule);
+        let resp = send(client, client.get(&rule.url))?;
+        resp.error_for_status_ref()?;
+        let body = read_text_bounded(resp)?;
+        let mut versions = find_versions(&rule, &body)?;
+        debug!("matched versions: {:?}", versions);
+        if let Some(mangle) = rule
+            .options
+            .get("uversionmangle")
+            .or_else(|| rule.options.get("versionmangle"))
+        {
+            versions = versions
+                .iter()
+                .map(|v| apply_mangle(mangle, v))
+                .collect::<Result<_>>()?;
+            debug!("after mangle: {:?}", versions);
+        }
+        if versions.is_empty() {
+            return Err(anyhow!("No version matches the pattern."));
+        }
+        self.order.sort(&mut versions)?;
+        debug!("after sort: {:?}", versions);
+
+        Ok(versions.first().unwrap().to_string())
+    }
+}
+
+#[test]
+fn test_parse_watch_file() {
+    let rule = parse_watch_file(
+        "# comment\n\
+version=4\n\
+opts=\"uversionmangle=s/-rc/~rc/, searchmode=plain\" \\\n\
+  https://example.com/releases/ @PACKAGE@@ANY_VERSION@@ARCHIVE_EXT@\n\
+https://example.com/other/ other-(\\d+)\\.tar\\.gz\n",
+        Some("foo"),
+    )
+    .unwrap();
+    assert_eq!(rule.url, "https://example.com/releases");
+    assert_eq!(rule.options["uversionmangle"], "s/-rc/~rc/");
+    assert_eq!(rule.options["searchmode"], "plain");
+    assert!(rule.pattern.starts_with(r"foo[-_]?v?(\d"));
+    // the pattern in the URL
+    let rule = parse_watch_line(
+        r"opts=uversionmangle=s/_/./g https://example.com/files/foo-(\d\S+)\.tar\.gz",
+        None,
+    )
+    .unwrap();
+    assert_eq!(rule.url, "https://example.com/files");
+    assert_eq!(rule.pattern, r"foo-(\d\S+)\.tar\.gz");
+    assert_eq!(rule.options["uversionmangle"], "s/_/./g");
+    assert!(parse_watch_file("version=2\nhttps://example.com/ foo-(.+)", None).is_err());
+    assert!(parse_watch_line("https://example.com/ @PACKAGE@-(.+)", None).is_err());
+    assert!(parse_watch_line("opts=mode=git https://example.com/foo.git HEAD", None).is_err());
+}
+
+#[test]
+fn test_apply_mangle() {
+    assert_eq!(apply_mangle("s/_/./g", "1_2_3").unwrap(), "1.2.3");
+    assert_eq!(apply_mangle("s/_/./", "1_2_3").unwrap(), "1.2_3");
+    assert_eq!(
+        apply_mangle(r"s/(\d)rc(\d)/$1~rc$2/;s/^v//", "v1.0rc1").unwrap(),
+        "1.0~rc1"
+    );
+    assert_eq!(apply_mangle(r"s%RC%rc%i", "1.0rc1").unwrap(), "1.0rc1");
+    assert_eq!(apply_mangle(r"s/\//./g", "1/2").unwrap(), "1.2");
+    assert!(apply_mangle("tr/a-z/A-Z/", "abc").is_err());
+}
+
+#[test]
+fn test_find_versions() {
+    let body = r#"<html><body>
+<a href="foo-1.0.tar.gz">foo-1.0.tar.gz</a>
+<a href="/releases/foo-1.2.tar.gz">foo-1.2.tar.gz</a>
+<a href="https://example.com/releases/foo-1.10.tar.gz.asc">signature</a>
+<a href="bar-2.0.tar.gz">bar-2.0.tar.gz</a>
+</body></html>"#;
+    let rule = parse_watch_line(
+        "https://example.com/releases/ @PACKAGE@@ANY_VERSION@@ARCHIVE_EXT@",
+        Some("foo"),
+    )
+    .unwrap();
+    assert_eq!(find_versions(&rule, body).unwrap(), vec!["1.0", "1.2"]);
+    let rule = parse_watch_line(
+        r"opts=searchmode=plain https://example.com/releases/ foo-(\d+)\.(\d+)\.tar\.gz",
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        find_versions(&rule, body).unwrap(),
+        vec!["1.0", "1.0", "1.2", "1.2", "1.10"]
+    );
+    assert_eq!(super::CANDIDATES_CONSIDERED.with(|c| c.get()), Some(5));
+    // the template replaces the uscan-style joining of the groups
+    super::REPLACE_TEMPLATE.with(|t| *t.borrow_mut() = Some("$2.$1".to_string()));
+    let versions = find_versions(&rule, body);
+    super::REPLACE_TEMPLATE.with(|t| t.take());
+    assert_eq!(versions.unwrap(), vec!["0.1", "0.1", "2.1", "2.1", "10.1"]);
+}
+
+#[test]
+fn test_watch() {
+    let mut options = HashMap::new();
+    options.insert(
+        "watch".to_string(),
+        r"https://repo.aosc.io/aosc-l10n/ zh_CN_l10n_(.+?)\.pdf".to_string(),
+    );
+    let client = Client::new();
+    let checker = WatchChecker::new(&options).unwrap();
+    dbg!(checker.check(&client).unwrap());
+}
@@ -1,4 +1,5 @@
 use clap::{Arg, Command};
+use regex::Regex;
 
 pub fn build_cli() -> Command {
     Command::new("aosc-findupdate")
@@ -16,6 +17,15 @@ pub fn build_cli() -> Command {
                 .num_args(1)
                 .help("Log updated packages to a file"),
         )
+        .arg(
+            Arg::new("COLOR")
+                .long("color")
+                .num_args(1)
+                .value_name("WHEN")
+                .value_parser(["auto", "always", "never"])
+                .default_value("auto")
+                .help("Color the output: auto (on terminals, unless NO_COLOR is set), always or never"),
+        )
         .arg(
             Arg::new("FILE")
                 .short('f')
@@ -28,11 +38,37 @@ pub fn build_cli() -> Command {
                 .num_args(1)
                 .help("Use regular expression to filter which package to update"),
         )
+        .arg(
+            Arg::new("EXCLUDE")
+                .short('e')
+                .long("exclude")
+                .num_args(1)
+                .value_parser(parse_regex)
+                .help("Use regular expression to filter which package to skip, applied after -i"),
+        )
+        .arg(
+            Arg::new("SKIP_FILE")
+                .long("skip-file")
+                .num_args(1)
+                .value_name("FILE")
+                .help("Path to a list of packages to be skipped"),
+        )
+        .arg(
+            Arg::new("MODIFIED_WITHIN")
+                .long("modified-within")
+                .num_args(1)
+                .value_name("DAYS")
+                .value_parser(clap::value_parser!(u64))
+                .help("Only check the specs modified in the last DAYS days, not applied to -f lists"),
+        )
         .arg(
             Arg::new("DIR")
                 .short('d')
+                .long("dir")
                 .num_args(1)
-                .help("Specify the directory to the abbs tree"),
+                .action(clap::ArgAction::Append)
+                .value_delimiter(',')
+                .help("Specify the directory to the abbs tree, may be repeated (or comma-separated) to check several trees"),
         )
         .arg(
             Arg::new("COMPLY")
@@ -52,10 +88,357 @@ pub fn build_cli() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .help("Update checksum after version bump (please setup ciel and CIEL_INST environment variable)"),
         )
+        .arg(
+            Arg::new("SKIP_CHECKSUM_ON_WARNING")
+                .long("skip-checksum-on-warning")
+                .action(clap::ArgAction::SetTrue)
+                .requires("UPDATE_CHECKSUM")
+                .help("Do not update checksum for packages with hardcoded URLs or broken specs"),
+        )
+        .arg(
+            Arg::new("JOBS")
+                .long("jobs")
+                .num_args(1)
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("Number of packages to check in parallel (default: number of CPU cores)"),
+        )
+        .arg(
+            Arg::new("TIMEOUT")
+                .long("timeout")
+                .num_args(1)
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("30")
+                .help("Give up on each request after the given number of seconds"),
+        )
+        .arg(
+            Arg::new("BATCH_SIZE")
+                .long("batch-size")
+                .num_args(1)
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("50")
+                .requires("UPDATE_CHECKSUM")
+                .help("Number of packages to update checksum for in each acbs-build invocation"),
+        )
         .arg(
             Arg::new("JSON")
                 .short('j')
                 .num_args(1)
-                .help("JSON output updated package list"),
+                .help("JSON output updated package list, use `-` to print it to stdout instead of the table"),
+        )
+        .arg(
+            Arg::new("JSON_V2")
+                .long("json-v2")
+                .action(clap::ArgAction::SetTrue)
+                .requires("JSON")
+                .help("Use the object schema with report metadata for JSON output"),
+        )
+        .arg(
+            Arg::new("FORMAT")
+                .long("format")
+                .num_args(1)
+                .value_parser(["table", "csv", "tsv"])
+                .default_value("table")
+                .help("Format of the results printed to stdout, csv and tsv print one name,before,after,warnings row per package"),
+        )
+        .arg(
+            Arg::new("SHOW_PATH")
+                .long("show-path")
+                .action(clap::ArgAction::SetTrue)
+                .help("Show the path of each updated package in the tree"),
+        )
+        .arg(
+            Arg::new("WARN_DUPLICATE_NAMES")
+                .long("warn-duplicate-names")
+                .action(clap::ArgAction::SetTrue)
+                .help("Warn about packages sharing the same name in different sections"),
+        )
+        .arg(
+            Arg::new("PRINT_SCHEMA")
+                .long("print-schema")
+                .action(clap::ArgAction::SetTrue)
+                .help("Print the JSON Schema of the JSON output and exit"),
+        )
+        .arg(
+            Arg::new("DIFF")
+                .long("diff")
+                .action(clap::ArgAction::SetTrue)
+                .help("Print the changes made to the specs (or that would be made in dry-run mode) as unified diffs"),
+        )
+        .arg(
+            Arg::new("TIMINGS")
+                .long("timings")
+                .action(clap::ArgAction::SetTrue)
+                .help("Record how long each package took to check, and list the slowest ones at the end"),
+        )
+        .arg(
+            Arg::new("DIFFSTAT")
+                .long("diffstat")
+                .action(clap::ArgAction::SetTrue)
+                .help("Print the number of changed specs and lines"),
+        )
+        .arg(
+            Arg::new("KEEP_REL")
+                .long("keep-rel")
+                .action(clap::ArgAction::SetTrue)
+                .help("Keep the REL line instead of removing it after version bump"),
+        )
+        .arg(
+            Arg::new("BACKUP")
+                .long("backup")
+                .action(clap::ArgAction::SetTrue)
+                .help("Keep a copy of each spec as spec.bak before updating it"),
+        )
+        .arg(
+            Arg::new("RESET_REL")
+                .long("reset-rel")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("KEEP_REL")
+                .help("Reset the REL line to REL=0 instead of removing it after version bump"),
+        )
+        .arg(
+            Arg::new("ONLY_UPGRADES")
+                .long("only-upgrades")
+                .action(clap::ArgAction::SetTrue)
+                .help("Only update packages whose new version is strictly greater than the current one"),
+        )
+        .arg(
+            Arg::new("FAIL_ON_NO_UPDATE")
+                .long("fail-on-no-update")
+                .action(clap::ArgAction::SetTrue)
+                .help("Exit with a non-zero status if no package was updated (intended for targeted checks)"),
+        )
+        .arg(
+            Arg::new("FAIL_FAST")
+                .long("fail-fast")
+                .action(clap::ArgAction::SetTrue)
+                .overrides_with("CONTINUE_ON_ERROR")
+                .help("Abort the run on the first failed check, exiting with status 3"),
+        )
+        .arg(
+            Arg::new("CONTINUE_ON_ERROR")
+                .long("continue-on-error")
+                .action(clap::ArgAction::SetTrue)
+                .overrides_with("FAIL_FAST")
+                .help("Keep checking the other packages when a check fails (default)"),
+        )
+        .arg(
+            Arg::new("STRICT")
+                .long("strict")
+                .visible_alias("check")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("FAIL_ON_NO_UPDATE")
+                .help("Exit with status 2 if any update was found, or 3 if any check failed"),
+        )
+        .arg(
+            Arg::new("STRICT_VERSION_FORMAT")
+                .long("strict-version-format")
+                .num_args(1)
+                .value_name("REGEX")
+                .value_parser(parse_regex)
+                .help("Refuse to update packages whose new version does not match the regular expression"),
         )
+        .arg(
+            Arg::new("SCHEME")
+                .long("scheme")
+                .num_args(1)
+                .value_name("TYPE")
+                .value_parser(crate::filter::VERSIONING_TYPE_NAMES.to_vec())
+                .help("Only check packages whose current version uses the specified versioning scheme"),
+        )
+        .arg(
+            Arg::new("EXPLAIN")
+                .long("explain")
+                .num_args(1)
+                .value_name("PACKAGE")
+                .help("Check only the specified package and explain each step in detail (implies --dry-run)"),
+        )
+        .arg(
+            Arg::new("REQUIRE_HTTPS")
+                .long("require-https")
+                .action(clap::ArgAction::SetTrue)
+                .help("Refuse to check upstreams over insecure protocols (plain HTTP or FTP)"),
+        )
+        .arg(
+            Arg::new("ALLOW_INSECURE_HOST")
+                .long("allow-insecure-host")
+                .num_args(1)
+                .value_name("HOST")
+                .action(clap::ArgAction::Append)
+                .requires("REQUIRE_HTTPS")
+                .help("Allow accessing the host insecurely even if --require-https is set"),
+        )
+        .arg(
+            Arg::new("CHECK_URLS_ONLY")
+                .long("check-urls-only")
+                .action(clap::ArgAction::SetTrue)
+                .help("Only check whether the upstream URLs are reachable, without checking versions"),
+        )
+        .arg(
+            Arg::new("DEDUP")
+                .long("dedup-by-upstream")
+                .action(clap::ArgAction::SetTrue)
+                .help("Check each unique upstream only once and share the result between packages"),
+        )
+        .arg(
+            Arg::new("FULL_PARSE")
+                .long("full-parse")
+                .action(clap::ArgAction::SetTrue)
+                .help("Always evaluate the whole spec instead of only reading the version and checker variables"),
+        )
+        .arg(
+            Arg::new("MAX_CANDIDATES")
+                .long("max-candidates")
+                .num_args(1)
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("10000")
+                .help("Maximum number of candidate versions sorted for each package"),
+        )
+        .arg(
+            Arg::new("MAX_CANDIDATES_ACTION")
+                .long("max-candidates-action")
+                .num_args(1)
+                .value_name("ACTION")
+                .value_parser(["truncate", "error"])
+                .default_value("truncate")
+                .help("Keep the lexically latest candidates or fail when there are more than --max-candidates"),
+        )
+        .arg(
+            Arg::new("PATCH_OUT")
+                .long("patch-out")
+                .num_args(1)
+                .value_name("FILE")
+                .help("Write the changes made to the specs as a patch for `git apply`"),
+        )
+        .arg(
+            Arg::new("TEMPLATE_OUT")
+                .long("template-out")
+                .num_args(1)
+                .value_name("FILE")
+                .requires("TEMPLATE")
+                .help("Render the template once per updated package and write the output to a file"),
+        )
+        .arg(
+            Arg::new("TEMPLATE")
+                .long("template")
+                .num_args(1)
+                .value_name("FILE")
+                .requires("TEMPLATE_OUT")
+                .help("Template used with --template-out, placeholders: {name}, {before}, {after}, {path}, {type}"),
+        )
+        .arg(
+            Arg::new("DNS_CACHE")
+                .long("dns-cache")
+                .action(clap::ArgAction::SetTrue)
+                .help("Share one HTTP client between all workers to reuse resolved hosts and connections"),
+        )
+        .arg(
+            Arg::new("SERIAL_WRITES")
+                .long("serial-writes")
+                .action(clap::ArgAction::SetTrue)
+                .help("Write the updated specs one at a time, while still checking in parallel"),
+        )
+        .arg(
+            Arg::new("DEFAULT_SORT")
+                .long("default-sort")
+                .action(clap::ArgAction::SetTrue)
+                .help("Sort the versions by default (as if sort_version=true), unless a spec sets sort_version=false"),
+        )
+        .arg(
+            Arg::new("RATE")
+                .long("rate")
+                .num_args(1)
+                .value_name("RPS")
+                .value_parser(parse_rate)
+                .help("Send at most RPS requests per second, across all upstreams"),
+        )
+        .arg(
+            Arg::new("RATE_HOST")
+                .long("rate-host")
+                .action(clap::ArgAction::SetTrue)
+                .requires("RATE")
+                .help("Apply the --rate limit to each host separately"),
+        )
+        .arg(
+            Arg::new("PER_HOST")
+                .long("per-host")
+                .num_args(1)
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("Send at most N concurrent requests to each host"),
+        )
+        .arg(
+            Arg::new("USER_AGENT")
+                .long("user-agent")
+                .num_args(1)
+                .value_name("UA")
+                .help("Send UA as the User-Agent of all requests, except for the git checker [env: FINDUPDATE_USER_AGENT]"),
+        )
+        .arg(
+            Arg::new("GIT_VERSION")
+                .long("git-version")
+                .num_args(1)
+                .value_name("VERSION")
+                .value_parser(parse_git_version)
+                .help("Make the git checker identify itself as git/VERSION [default: 2.31.1]"),
+        )
+        .arg(
+            Arg::new("MAX_BODY")
+                .long("max-body")
+                .num_args(1)
+                .value_name("SIZE")
+                .value_parser(parse_size)
+                .help("Give up on scraped pages larger than SIZE bytes (K, M and G suffixes accepted) [default: 10M]"),
+        )
+        .arg(
+            Arg::new("RETRIES")
+                .long("retries")
+                .num_args(1)
+                .value_name("N")
+                .default_value("2")
+                .value_parser(clap::value_parser!(u32))
+                .help("Retry requests failing with connection errors, timeouts, 429 or 5xx up to N times"),
+        )
+}
+
+/// Lowest accepted `--rate`, i.e. one request every 1000 seconds, so that the interval between
+/// the requests stays representable
+const MIN_RATE: f64 = 0.001;
+
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate >= MIN_RATE => Ok(rate),
+        _ => Err(format!("must be a number no less than {}", MIN_RATE)),
+    }
+}
+
+fn parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|e| e.to_string())
+}
+
+fn parse_git_version(s: &str) -> Result<String, String> {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || ".-_".contains(c))
+    {
+        Ok(s.to_string())
+    } else {
+        Err("must be a version number, e.g. 2.45.2".to_string())
+    }
+}
+
+fn parse_size(s: &str) -> Result<u64, String> {
+    let (number, unit) = match s.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&s[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&s[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    match number.parse::<u64>().ok().and_then(|n| n.checked_mul(unit)) {
+        Some(size) if size > 0 => Ok(size),
+        _ => Err("must be a positive size, e.g. 10M".to_string()),
+    }
 }
@@ -0,0 +1,208 @@
+//! Minimal line-based diff for summarizing changes made to the specs.
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum DiffOp<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Computes the line differences between `a` and `b` using the longest common subsequence
+pub(crate) fn diff_lines<'a>(a: &'a str, b: &'a str) -> Vec<DiffOp<'a>> {
+    let a = a.lines().collect::<Vec<_>>();
+    let b = b.lines().collect::<Vec<_>>();
+    // lcs[i][j] is the LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            ops.push(DiffOp::Equal(a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(DiffOp::Delete(a[i]));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert(b[j]));
+            j += 1;
+        }
+    }
+    ops.extend(a[i..].iter().map(|x| DiffOp::Delete(x)));
+    ops.extend(b[j..].iter().map(|x| DiffOp::Insert(x)));
+
+    ops
+}
+
+/// Counts the lines added and removed between `a` and `b`
+pub(crate) fn diffstat(a: &str, b: &str) -> (usize, usize) {
+    diff_lines(a, b)
+        .iter()
+        .fold((0, 0), |(added, removed), op| match op {
+            DiffOp::Equal(_) => (added, removed),
+            DiffOp::Delete(_) => (added, removed + 1),
+            DiffOp::Insert(_) => (added + 1, removed),
+        })
+}
+
+/// Formats a hunk range, where an empty range refers to the line before it
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start - 1),
+        1 => start.to_string(),
+        _ => format!("{},{}", start, len),
+    }
+}
+
+/// Formats the differences between `a` and `b` as unified diff hunks with `context` lines
+pub(crate) fn unified_diff(a: &str, b: &str, context: usize) -> String {
+    let mut ops = diff_lines(a, b);
+    // the last lines differ if only one of them ends with a newline
+    if a.ends_with('\n') != b.ends_with('\n') {
+        if let Some(&DiffOp::Equal(line)) = ops.last() {
+            ops.pop();
+            ops.push(DiffOp::Delete(line));
+            ops.push(DiffOp::Insert(line));
+        }
+    }
+    // line numbers in `a` and `b` of each operation
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut i, mut j) = (1, 1);
+    for op in &ops {
+        positions.push((i, j));
+        match op {
+            DiffOp::Equal(_) => (i, j) = (i + 1, j + 1),
+            DiffOp::Delete(_) => i += 1,
+            DiffOp::Insert(_) => j += 1,
+        }
+    }
+    let (a_lines, b_lines) = (i - 1, j - 1);
+    let changes = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffOp::Equal(_)))
+        .map(|(k, _)| k)
+        .collect::<Vec<_>>();
+
+    let mut output = String::new();
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(context);
+        // merge the changes separated by at most 2 * context unchanged lines
+        while k + 1 < changes.len() && changes[k + 1] - changes[k] <= 2 * context + 1 {
+            k += 1;
+        }
+        let end = (changes[k] + context + 1).min(ops.len());
+        k += 1;
+
+        let hunk = &ops[start..end];
+        let old_len = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Insert(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Delete(_)))
+            .count();
+        let (old_start, new_start) = positions[start];
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_len),
+            hunk_range(new_start, new_len)
+        ));
+        for (op, (i, j)) in hunk.iter().zip(&positions[start..end]) {
+            let (prefix, line, in_a, in_b) = match op {
+                DiffOp::Equal(line) => (' ', line, true, true),
+                DiffOp::Delete(line) => ('-', line, true, false),
+                DiffOp::Insert(line) => ('+', line, false, true),
+            };
+            output.push(prefix);
+            output.push_str(line);
+            output.push('\n');
+            if (in_a && *i == a_lines && !a.ends_with('\n'))
+                || (in_b && *j == b_lines && !b.ends_with('\n'))
+            {
+                output.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    output
+}
+
+/// Formats the changes made to the file at `path` (relative to the tree) as a patch for `git apply`
+pub(crate) fn git_patch(path: &str, a: &str, b: &str) -> String {
+    if a == b {
+        return String::new();
+    }
+
+    format!(
+        "diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n{}",
+        unified_diff(a, b, 3)
+    )
+}
+
+#[test]
+fn test_diff_lines() {
+    let a = "VER=1.0\nREL=1\nSRCS=foo\n";
+    let b = "VER=1.1\nSRCS=foo\n";
+    assert_eq!(
+        diff_lines(a, b),
+        vec![
+            DiffOp::Delete("VER=1.0"),
+            DiffOp::Delete("REL=1"),
+            DiffOp::Insert("VER=1.1"),
+            DiffOp::Equal("SRCS=foo"),
+        ]
+    );
+    assert_eq!(diffstat(a, b), (1, 2));
+    assert_eq!(diffstat(a, a), (0, 0));
+}
+
+#[test]
+fn test_unified_diff() {
+    let a = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+    let b = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\n";
+    assert_eq!(
+        unified_diff(a, b, 3),
+        "@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n@@ -11,3 +11,4 @@\n k\n l\n m\n+n\n"
+    );
+    // close changes share a hunk
+    let b = "a\nB\nc\nd\ne\nf\ng\nH\ni\nj\nk\nl\nm\n";
+    assert_eq!(
+        unified_diff(a, b, 3),
+        "@@ -1,11 +1,11 @@\n a\n-b\n+B\n c\n d\n e\n f\n g\n-h\n+H\n i\n j\n k\n"
+    );
+    assert_eq!(unified_diff("a\n", "", 3), "@@ -1 +0,0 @@\n-a\n");
+    assert_eq!(unified_diff("", "a\n", 3), "@@ -0,0 +1 @@\n+a\n");
+    assert_eq!(
+        unified_diff("a\nb", "a\nb\n", 3),
+        "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+    );
+    assert_eq!(unified_diff(a, a, 3), "");
+}
+
+#[test]
+fn test_git_patch() {
+    assert_eq!(
+        git_patch("app-admin/foo/spec", "VER=1.0\nREL=1\n", "VER=1.1\n"),
+        "diff --git a/app-admin/foo/spec b/app-admin/foo/spec\n\
+         --- a/app-admin/foo/spec\n\
+         +++ b/app-admin/foo/spec\n\
+         @@ -1,2 +1 @@\n\
+         -VER=1.0\n\
+         -REL=1\n\
+         +VER=1.1\n"
+    );
+    assert_eq!(git_patch("foo/spec", "VER=1.0\n", "VER=1.0\n"), "");
+}
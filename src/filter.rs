@@ -38,7 +38,7 @@ pub trait VersionStr {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
-enum VersioningType {
+pub(crate) enum VersioningType {
     Normal,
     LetterNotation,
     Dashes,
@@ -47,7 +47,33 @@ enum VersioningType {
     Revision,
 }
 
-fn version_type(version_string: &str) -> VersioningType {
+/// Names of the versioning types accepted on the command line
+pub(crate) const VERSIONING_TYPE_NAMES: &[&str] = &[
+    "normal",
+    "letter-notation",
+    "dashes",
+    "underscores",
+    "release-types",
+    "revision",
+];
+
+impl std::str::FromStr for VersioningType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(VersioningType::Normal),
+            "letter-notation" => Ok(VersioningType::LetterNotation),
+            "dashes" => Ok(VersioningType::Dashes),
+            "underscores" => Ok(VersioningType::Underscores),
+            "release-types" => Ok(VersioningType::ReleaseTypes),
+            "revision" => Ok(VersioningType::Revision),
+            _ => Err(format!("Unknown versioning type: {}", s)),
+        }
+    }
+}
+
+pub(crate) fn version_type(version_string: &str) -> VersioningType {
     let matcher_letter_notation = Regex::new(REGEX_LETTER_NOTATION).unwrap();
     let matcher_dashes = Regex::new(REGEX_DASHES).unwrap();
     let matcher_underscores = Regex::new(REGEX_UNDERSCORES).unwrap();
@@ -110,6 +136,24 @@ impl VersionStr for str {
     }
 }
 
+/// Whether the versions are the same after normalization, differing only in case or separators
+///
+/// e.g. `1-2-3` and `1.2.3`
+pub fn is_same_version(a: &str, b: &str) -> bool {
+    a == b || a.compily_with_aosc() == b.compily_with_aosc()
+}
+
+#[test]
+fn test_is_same_version() {
+    assert!(is_same_version("1.2.3", "1.2.3"));
+    // current version vs. the upstream version under compliance mode
+    assert!(is_same_version("1-2-3", &"1.2.3".compily_with_aosc()));
+    assert!(is_same_version("2023_07_18", "2023.07.18"));
+    assert!(is_same_version("1.0-RC1", "1.0~rc1"));
+    assert!(!is_same_version("1.2.3", "1.2.4"));
+    assert!(!is_same_version("1-2-3", "1.2"));
+}
+
 #[test]
 fn test_version_type() {
     let normal_version_str = &"1.2.3";
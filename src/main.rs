@@ -1,62 +1,253 @@
-use crate::filter::VersionStr;
+use crate::checker::UpstreamVersion;
+use crate::filter::{is_same_version, version_type, VersionStr, VersioningType};
 use aho_corasick::AhoCorasickBuilder;
 use anyhow::{anyhow, Result};
-use log::{info, warn};
-use owo_colors::colored::*;
-use rayon::prelude::*;
+use chrono::{SecondsFormat, Utc};
+use log::{debug, info, warn, LevelFilter};
+use owo_colors::{OwoColorize, Style};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
 use regex::Regex;
-use reqwest::blocking::Client;
+use reqwest::{blocking::Client, header::HeaderValue, StatusCode};
 use serde::Serialize;
 use std::{
     borrow::Cow,
-    collections::HashMap,
-    fs::{File, OpenOptions},
-    io::{Read, Seek, SeekFrom, Write},
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display},
+    fs::File,
+    io::{BufWriter, IsTerminal, Write},
     path::{Path, PathBuf},
     process::Command,
     sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
     },
+    time::{Duration, Instant, SystemTime},
 };
 use version_compare::{compare_to, Cmp};
 use walkdir::WalkDir;
 
 mod checker;
 mod cli;
+mod diff;
 mod filter;
 mod parser;
+mod template;
 
 const VCS_VERSION_NUMBERS: &[&str] = &["+git", "+hg", "+svn", "+bzr"];
+/// Warnings indicating the package should not be touched any further
+const BLOCKING_WARNINGS: &[&str] = &["Hardcoded URLs detected", "Modified spec is broken"];
+
+/// Upstream check results shared by packages with the same checker config
+type UpstreamCache = HashMap<String, Result<UpstreamVersion, String>>;
+
+/// Options controlling how each package is checked and updated
+struct WorkerOptions<'a> {
+    dry_run: bool,
+    comply: bool,
+    explain: bool,
+    rel: RelAction,
+    /// Keep a copy of the original spec as `spec.bak`
+    backup: bool,
+    only_upgrades: bool,
+    full_parse: bool,
+    cache: Option<&'a UpstreamCache>,
+    version_format: Option<Regex>,
+    /// Held while writing the specs, so that only one is written at a time
+    write_lock: Option<&'a Mutex<()>>,
+}
 
 #[derive(Debug)]
-struct CheckerResult {
-    name: String,
+struct SpecChange {
     before: String,
     after: String,
-    warnings: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
-struct CheckResultOutput {
+#[derive(Debug)]
+struct CheckerResult {
     name: String,
+    /// Type of the checker that found the version
+    checker_type: String,
     before: String,
     after: String,
-    path: String,
+    /// Upstream version before compliance transformation
+    raw_upstream: String,
+    /// Number of candidate versions the checker evaluated
+    candidates_considered: usize,
     warnings: Vec<String>,
+    change: Option<SpecChange>,
+    /// Path of the package, only resolved for the updated packages when it is needed
+    path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckResultOutput<'a> {
+    name: &'a str,
+    before: &'a str,
+    after: &'a str,
+    raw_upstream: &'a str,
+    candidates_considered: usize,
+    path: &'a str,
+    warnings: &'a [String],
+    checked_at: &'a str,
+    /// Time spent checking the package, only recorded with `--timings`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+}
+
+/// Serializes the items as a JSON array as they are produced, without collecting them first
+struct JsonStream<I>(RefCell<Option<I>>);
+
+impl<I> JsonStream<I> {
+    fn new(items: I) -> Self {
+        JsonStream(RefCell::new(Some(items)))
+    }
+}
+
+impl<I: Iterator<Item = T>, T: Serialize> Serialize for JsonStream<I> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let items = self
+            .0
+            .borrow_mut()
+            .take()
+            .ok_or_else(|| serde::ser::Error::custom("items can only be serialized once"))?;
+
+        serializer.collect_seq(items)
+    }
+}
+
+/// Object schema of the JSON output, used with `--json-v2`
+#[derive(Serialize)]
+struct JsonOutputV2<'a, R> {
+    generated_at: &'a str,
+    results: R,
+}
+
+/// JSON Schema of the `--json` and `--json-v2` output, keep in sync with the structs above
+fn output_schema() -> serde_json::Value {
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "aosc-findupdate output",
+        "anyOf": [
+            { "$ref": "#/$defs/results" },
+            { "$ref": "#/$defs/output_v2" }
+        ],
+        "$defs": {
+            "result": {
+                "description": "An updated package",
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Package name" },
+                    "before": { "type": "string", "description": "Version before the update" },
+                    "after": { "type": "string", "description": "Version after the update" },
+                    "raw_upstream": {
+                        "type": "string",
+                        "description": "Upstream version before compliance transformation"
+                    },
+                    "candidates_considered": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Number of candidate versions the checker evaluated before picking one"
+                    },
+                    "path": { "type": "string", "description": "Path of the package in the tree" },
+                    "warnings": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Issues found while updating the package"
+                    },
+                    "checked_at": {
+                        "type": "string",
+                        "format": "date-time",
+                        "description": "When the check was started"
+                    },
+                    "duration_ms": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Time spent checking the package in milliseconds, only present with --timings"
+                    }
+                },
+                "required": [
+                    "name",
+                    "before",
+                    "after",
+                    "raw_upstream",
+                    "candidates_considered",
+                    "path",
+                    "warnings",
+                    "checked_at"
+                ],
+                "additionalProperties": false
+            },
+            "results": {
+                "description": "Output of --json",
+                "type": "array",
+                "items": { "$ref": "#/$defs/result" }
+            },
+            "output_v2": {
+                "description": "Output of --json with --json-v2",
+                "type": "object",
+                "properties": {
+                    "generated_at": {
+                        "type": "string",
+                        "format": "date-time",
+                        "description": "When the report was generated"
+                    },
+                    "results": { "$ref": "#/$defs/results" }
+                },
+                "required": ["generated_at", "results"],
+                "additionalProperties": false
+            }
+        }
+    })
 }
 
-fn collect_spec(dir: &Path) -> Result<Vec<PathBuf>> {
+/// Whether the output is colored, decided by `--color`
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Text styled only if the output is colored
+struct Painted<T>(T, Style);
+
+impl<T: Display> Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if COLOR.load(Ordering::Relaxed) {
+            self.0.style(self.1).fmt(f)
+        } else {
+            self.0.fmt(f)
+        }
+    }
+}
+
+fn paint<T: Display>(text: T, style: Style) -> Painted<T> {
+    Painted(text, style)
+}
+
+/// Resolves `--color`: in `auto` mode, colors are used on terminals unless `NO_COLOR` is set
+/// (to a non-empty value) or the terminal is dumb
+fn use_color(choice: &str, is_terminal: bool, no_color: Option<&str>, term: Option<&str>) -> bool {
+    match choice {
+        "always" => true,
+        "never" => false,
+        _ => is_terminal && no_color.is_none_or(|v| v.is_empty()) && term != Some("dumb"),
+    }
+}
+
+/// Collects the specs in the tree, skipping those last modified before `modified_since` if set
+fn collect_spec(dir: &Path, modified_since: Option<SystemTime>) -> Result<Vec<PathBuf>> {
     let walker = WalkDir::new(dir).min_depth(1).max_depth(3);
     let result = walker
         .into_iter()
         .filter_map(|x| {
             let entry = x.ok()?;
-            if entry.file_name() == "spec" {
-                entry.path().canonicalize().ok()
-            } else {
-                None
+            if entry.file_name() != "spec" {
+                return None;
             }
+            if let Some(since) = modified_since {
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                if modified < since {
+                    return None;
+                }
+            }
+            entry.path().canonicalize().ok()
         })
         .collect();
 
@@ -76,34 +267,152 @@ fn normalize_filename(path: &Path) -> Cow<str> {
     p.to_string_lossy()
 }
 
+/// What to do with the `REL` line when bumping the version
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RelAction {
+    /// Remove the line
+    Remove,
+    /// Leave the line as-is
+    Keep,
+    /// Reset the release number to 0 if the version changed
+    Reset,
+}
+
+fn replace_version(content: &str, new: &str, replace_upstream_ver: bool, rel: RelAction) -> String {
+    // only match assignments at the start of a line, not comments or other variables
+    let replaced = if replace_upstream_ver {
+        let replace = Regex::new("(?m)^UPSTREAM_VER=.+").unwrap();
+        replace.replace(content, format!("UPSTREAM_VER={}", new))
+    } else {
+        let replace = Regex::new("(?m)^VER=.+").unwrap();
+        replace.replace(content, format!("VER={}", new))
+    };
+    match rel {
+        RelAction::Keep => replaced.to_string(),
+        // the version is unchanged, so is the release
+        RelAction::Reset if replaced == content => replaced.to_string(),
+        RelAction::Reset => {
+            let reset_rel = Regex::new("(?m)^REL=.+").unwrap();
+            reset_rel.replace(&replaced, "REL=0").to_string()
+        }
+        RelAction::Remove => {
+            let replace_rel = Regex::new("(?m)^REL=.*\n?").unwrap();
+            replace_rel.replace(&replaced, "").to_string()
+        }
+    }
+}
+
 fn update_version<P: AsRef<Path>>(
     new: &str,
     spec: P,
     replace_upstream_ver: bool,
-) -> Result<String> {
-    let mut f = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .open(spec.as_ref())?;
-    let mut content = String::new();
-    f.read_to_string(&mut content)?;
-    let replace_rel = Regex::new("REL=.+\\s+").unwrap();
+    rel: RelAction,
+    backup: bool,
+) -> Result<(String, String)> {
+    let content = std::fs::read_to_string(spec.as_ref())?;
+    let replaced = replace_version(&content, new, replace_upstream_ver, rel);
+    write_spec(spec.as_ref(), &replaced, backup)?;
 
-    let replaced = if replace_upstream_ver {
-        let replace = Regex::new("UPSTREAM_VER=.+").unwrap();
-        replace.replace(&content, format!("UPSTREAM_VER={}", new))
+    Ok((content, replaced))
+}
+
+/// Warns if the new version is older than the current one. For `UPSTREAM_VER` packages, the raw
+/// upstream version is compared, since `VER` may carry distribution-specific suffixes
+fn downgrade_warning(
+    current: &str,
+    new: &str,
+    raw_upstream: &str,
+    is_upstream_ver: bool,
+) -> Option<String> {
+    let new = if is_upstream_ver { raw_upstream } else { new };
+    match compare_to(current, new, Cmp::Gt) {
+        Ok(true) if is_upstream_ver => Some(format!(
+            "Upstream version went backwards ({} -> {})",
+            current, new
+        )),
+        Ok(true) => Some(format!(
+            "Possible downgrade from the current version ({} -> {})",
+            current, new
+        )),
+        Ok(false) => None,
+        Err(_) => Some(format!(
+            "Versions not comparable: `{}` and `{}`",
+            current, new
+        )),
+    }
+}
+
+/// Leading numeric component of a version, e.g. `2` for `v2.3.1`
+fn major_version(version: &str) -> Option<u64> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let end = version
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(version.len());
+
+    version[..end].parse().ok()
+}
+
+/// Warns if the major version jumps by more than one, which is more often an unrelated tag
+/// matched by mistake than a real release. Calendar versions (e.g. `20240101`) are not checked
+fn version_jump_warning(current: &str, new: &str) -> Option<String> {
+    let (from, to) = (major_version(current)?, major_version(new)?);
+    if from >= 1000 && to >= 1000 {
+        return None;
+    }
+    if to > from.saturating_add(1) {
+        Some(format!(
+            "Large version jump, verify manually ({} -> {})",
+            current, new
+        ))
     } else {
-        let replace = Regex::new("VER=.+").unwrap();
-        replace.replace(&content, format!("VER={}", new))
-    };
-    let replaced = replace_rel.replace(&replaced, "");
+        None
+    }
+}
 
-    f.seek(SeekFrom::Start(0))?;
-    let bytes = replaced.as_bytes();
-    f.write_all(bytes)?;
-    f.set_len(bytes.len() as u64)?;
+/// Parses the updated spec, restoring the original content if it is broken so that a bad
+/// rewrite never stays in the tree. Returns the parse errors
+fn reparse_or_revert(
+    spec: &Path,
+    original: &str,
+    modified: &str,
+    context: &mut HashMap<String, String>,
+) -> Result<Vec<String>> {
+    match abbs_meta_apml::parse(modified, context) {
+        Ok(_) => Ok(vec![]),
+        Err(err) => {
+            write_spec(spec, original, false)?;
+            Ok(err.into_iter().map(|e| e.to_string()).collect())
+        }
+    }
+}
 
-    Ok(replaced.to_string())
+/// Replaces the spec atomically by writing a temporary file next to it and renaming it over the
+/// original, so that the spec is never left half-written. With `backup`, the original is kept as
+/// `spec.bak`
+fn write_spec(spec: &Path, content: &str, backup: bool) -> Result<()> {
+    let file_name = spec
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid spec path: {}", spec.display()))?
+        .to_string_lossy();
+    let temp = spec.with_file_name(format!(".{}.tmp", file_name));
+    let replace = || -> Result<()> {
+        let mut f = File::create(&temp)?;
+        f.write_all(content.as_bytes())?;
+        f.set_permissions(std::fs::metadata(spec)?.permissions())?;
+        f.sync_all()?;
+        if backup {
+            std::fs::copy(spec, spec.with_file_name(format!("{}.bak", file_name)))?;
+        }
+        std::fs::rename(&temp, spec)?;
+        Ok(())
+    };
+    if let Err(e) = replace() {
+        // don't leave the temporary file behind in the tree
+        std::fs::remove_file(&temp).ok();
+        return Err(e);
+    }
+
+    Ok(())
 }
 
 fn validate_urls(a: &HashMap<String, String>, b: &HashMap<String, String>) -> bool {
@@ -125,13 +434,304 @@ fn validate_urls(a: &HashMap<String, String>, b: &HashMap<String, String>) -> bo
     false
 }
 
+/// Parses `CHKUPDATE` and the fallback checkers (`CHKUPDATE__0`, `CHKUPDATE__1`, ...) in order,
+/// along with the name of the variable each config comes from
+fn get_check_configs<P: AsRef<Path>>(
+    s: &HashMap<String, String>,
+    spec: P,
+) -> Result<Vec<(String, HashMap<String, String>)>> {
+    let names = std::iter::once("CHKUPDATE".to_string())
+        .chain((0..).map(|i| format!("CHKUPDATE__{}", i)))
+        .enumerate()
+        // `CHKUPDATE` itself is optional if there are fallback checkers
+        .take_while(|(i, name)| *i == 0 || s.contains_key(name))
+        .filter(|(_, name)| s.contains_key(name));
+    let mut configs = Vec::new();
+    for (_, name) in names {
+        let config_line = s[&name].to_owned() + ";"; // compensate for the parser quirk
+        let mut config = parser::parse_check_update(&mut config_line.as_str())?;
+        // `${name}` in `replace` refers to a named capture group, not to a variable
+        for (key, value) in config
+            .iter_mut()
+            .filter(|(k, _)| *k != "type" && *k != "replace")
+        {
+            let (expanded, missing) = parser::expand_variables(value, s);
+            for variable in missing {
+                warn!(
+                    "{}: {} references an undefined variable ${{{}}} in `{}`, left as-is",
+                    spec.as_ref().display(),
+                    name,
+                    variable,
+                    key
+                );
+            }
+            *value = expanded;
+        }
+        configs.push((name, config));
+    }
+    if configs.is_empty() {
+        return Err(anyhow!(
+            "{}: 'CHKUPDATE' field is missing, cannot continue!",
+            spec.as_ref().display()
+        ));
+    }
+
+    Ok(configs)
+}
+
+/// Parses the config of the primary checker
+fn get_check_config<P: AsRef<Path>>(
+    s: &HashMap<String, String>,
+    spec: P,
+) -> Result<HashMap<String, String>> {
+    let (_, config) = get_check_configs(s, spec)?.swap_remove(0);
+
+    Ok(config)
+}
+
+/// Tries the checkers in order until one of them succeeds, returning its index and result
+fn check_with_fallback<T, F>(
+    configs: &[(String, HashMap<String, String>)],
+    mut check: F,
+) -> Result<(usize, T)>
+where
+    F: FnMut(&HashMap<String, String>) -> Result<T>,
+{
+    let mut errors = Vec::new();
+    for (i, (name, config)) in configs.iter().enumerate() {
+        match check(config) {
+            Ok(version) => return Ok((i, version)),
+            Err(e) => {
+                debug!("{} failed: {:?}", name, e);
+                errors.push(e);
+            }
+        }
+    }
+    if errors.len() == 1 {
+        return Err(errors.pop().unwrap());
+    }
+    let errors = configs
+        .iter()
+        .zip(errors)
+        .map(|((name, _), e)| format!("{}: {:?}", name, e))
+        .collect::<Vec<_>>();
+
+    Err(anyhow!(
+        "All {} checkers failed:\n{}",
+        errors.len(),
+        errors.join("\n")
+    ))
+}
+
+/// Builds a key identifying the upstream described by the checker config
+fn upstream_key(config: &HashMap<String, String>) -> String {
+    let mut pairs = config.iter().collect::<Vec<_>>();
+    pairs.sort_unstable();
+
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// How the HTTP clients of the workers are created
+struct ClientOptions {
+    /// Timeout of each request
+    timeout: Duration,
+    /// Client shared by all workers, if any
+    shared: Option<Client>,
+    /// User-Agent of the requests not setting their own, none if unset
+    user_agent: Option<String>,
+}
+
+impl ClientOptions {
+    fn new(timeout: Duration, shared: bool, user_agent: Option<String>) -> Self {
+        let mut options = ClientOptions {
+            timeout,
+            shared: None,
+            user_agent,
+        };
+        if shared {
+            options.shared = Some(options.build());
+        }
+
+        options
+    }
+
+    fn build(&self) -> Client {
+        let mut builder = Client::builder().timeout(self.timeout);
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        builder.build().expect("Failed to create the HTTP client.")
+    }
+
+    /// Creates the HTTP client of each worker. With `--dns-cache`, all workers share the same client
+    /// so that the resolved addresses and the open connections are reused between them
+    fn worker_client(&self) -> Client {
+        self.shared.clone().unwrap_or_else(|| self.build())
+    }
+}
+
+/// Whether the error was caused by a request timing out
+fn is_timeout(error: &anyhow::Error) -> bool {
+    error.chain().any(|e| {
+        e.downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_timeout())
+    })
+}
+
+/// Checks each unique upstream among the given specs only once
+fn check_unique_upstreams(
+    files: &[PathBuf],
+    full_parse: bool,
+    clients: &ClientOptions,
+) -> UpstreamCache {
+    let configs = files
+        .par_iter()
+        .filter_map(|f| {
+            let s = parser::parse_spec(f, full_parse).ok()?;
+            get_check_config(&s, f).ok()
+        })
+        .collect::<Vec<_>>();
+    let mut unique = HashMap::new();
+    for config in configs.iter() {
+        unique.entry(upstream_key(config)).or_insert(config);
+    }
+    info!(
+        "Checking {} unique upstreams for {} packages ({} checks saved) ...",
+        unique.len(),
+        configs.len(),
+        configs.len() - unique.len()
+    );
+
+    unique
+        .into_par_iter()
+        .map_init(
+            || clients.worker_client(),
+            |c, (key, config)| {
+                let result = checker::check_update(config, c).map_err(|e| format!("{:?}", e));
+                (key, result)
+            },
+        )
+        .collect()
+}
+
+/// Checks whether the checker endpoints of the given specs are still reachable
+fn check_url_health(files: &[PathBuf], full_parse: bool, clients: &ClientOptions) {
+    let results = files
+        .par_iter()
+        .map_init(
+            || clients.worker_client(),
+            |c, f| {
+                let name = normalize_name(f).to_string();
+                let url = parser::parse_spec(f, full_parse)
+                    .and_then(|s| get_check_config(&s, f))
+                    .and_then(|config| checker::endpoint(&config));
+                let url = match url {
+                    Ok(url) => url,
+                    Err(e) => return (name, String::new(), Err(e)),
+                };
+                let status = checker::check_url_health(c, &url);
+                (name, url, status)
+            },
+        )
+        .collect::<Vec<_>>();
+
+    println!("{:<30}{:<70}Status", "Name", "URL");
+    let mut unhealthy = 0;
+    for (name, url, status) in results {
+        let status = match status {
+            // the endpoint exists, but needs credentials
+            Ok(status) if status == StatusCode::UNAUTHORIZED => {
+                paint(status, Style::new().yellow()).to_string()
+            }
+            Ok(status) if status.is_client_error() || status.is_server_error() => {
+                unhealthy += 1;
+                paint(status, Style::new().red()).to_string()
+            }
+            Ok(status) => paint(status, Style::new().green()).to_string(),
+            Err(e) => {
+                unhealthy += 1;
+                paint(format!("{:#}", e), Style::new().red()).to_string()
+            }
+        };
+        println!(
+            "{:<30}{:<70}{}",
+            paint(&name, Style::new().cyan()),
+            url,
+            status
+        );
+    }
+    println!("\n{} of {} URLs are unhealthy.", unhealthy, files.len());
+}
+
+/// Checks the mirror specified by `mirror_url`, using the `git` checker unless `mirror_type` is set
+fn check_mirror(
+    config: &HashMap<String, String>,
+    mirror_url: &str,
+    client: &Client,
+) -> Result<String> {
+    let mut mirror_config = HashMap::new();
+    let mirror_type = config.get("mirror_type").map_or("git", |t| t.as_str());
+    mirror_config.insert("type".to_string(), mirror_type.to_string());
+    mirror_config.insert("url".to_string(), mirror_url.to_string());
+    for key in ["pattern", "patterns", "pattern_type", "anchor"] {
+        if let Some(value) = config.get(key) {
+            mirror_config.insert(key.to_string(), value.clone());
+        }
+    }
+    let version = checker::check_update(&mirror_config, client)?.version;
+    let version = version.trim();
+
+    Ok(version.strip_prefix('v').unwrap_or(version).to_string())
+}
+
+fn validate_version_format(version: &str, format: &Regex) -> Result<()> {
+    if !format.is_match(version) {
+        return Err(anyhow!(
+            "Version '{}' does not match the required format `{}`",
+            version,
+            format
+        ));
+    }
+
+    Ok(())
+}
+
+/// Makes sure the new version is strictly greater than the current one
+fn validate_upgrade(current: &str, new: &str) -> Result<()> {
+    match compare_to(new, current, Cmp::Gt) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(anyhow!(
+            "Refusing to write '{}', which is not newer than '{}'",
+            new,
+            current
+        )),
+        Err(_) => Err(anyhow!(
+            "Refusing to write '{}', which is not comparable with '{}'",
+            new,
+            current
+        )),
+    }
+}
+
+/// Whether the current version of the package uses the given versioning scheme
+fn matches_scheme(s: &HashMap<String, String>, scheme: VersioningType) -> bool {
+    s.get("UPSTREAM_VER")
+        .or_else(|| s.get("VER"))
+        .is_some_and(|v| version_type(v.trim()) == scheme)
+}
+
 fn check_update_worker<P: AsRef<Path>>(
     client: &Client,
     spec: P,
-    dry_run: bool,
-    mut comply: bool,
+    options: &WorkerOptions,
 ) -> Result<CheckerResult> {
-    let s = parser::parse_spec(spec.as_ref())?;
+    let s = parser::parse_spec(spec.as_ref(), options.full_parse)?;
+    let mut comply = options.comply;
     let mut is_upstream_ver = false;
     let current_version = if let Some(v) = s.get("UPSTREAM_VER") {
         comply = false;
@@ -147,18 +747,39 @@ fn check_update_worker<P: AsRef<Path>>(
     };
 
     let current_version = current_version.trim();
-    let config_line = s.get("CHKUPDATE").ok_or_else(|| {
-        anyhow!(
-            "{}: 'CHKUPDATE' field is missing, cannot continue!",
-            spec.as_ref().display()
-        )
-    })?;
+    let configs = get_check_configs(&s, spec.as_ref())?;
+    debug!("spec: {}", spec.as_ref().display());
+    for (name, config) in configs.iter() {
+        debug!("{} config: {:?}", name, config);
+    }
     let mut warnings = Vec::new();
-    let config_line = config_line.to_owned() + ";"; // compensate for the parser quirk
-    let config = parser::parse_check_update(&mut config_line.as_str())?;
-    let new_version = checker::check_update(&config, client)?;
+    let (index, upstream) = check_with_fallback(&configs, |config| {
+        match options.cache.and_then(|c| c.get(&upstream_key(config))) {
+            Some(Ok(version)) => Ok(version.clone()),
+            Some(Err(e)) => Err(anyhow!("{}", e)),
+            None => checker::check_update(config, client),
+        }
+    })?;
+    let (name, config) = &configs[index];
+    let checker_type = config.get("type").cloned().unwrap_or_default();
+    let candidates_considered = upstream.candidates_considered;
+    let new_version = upstream.version;
+    if index > 0 {
+        warnings.push(format!("Primary checker failed, used {}", name));
+    }
     let new_version = new_version.trim();
     let new_version = new_version.strip_prefix('v').unwrap_or(new_version);
+    let raw_upstream = new_version.to_string();
+    if let Some(mirror_url) = config.get("mirror_url") {
+        match check_mirror(config, mirror_url, client) {
+            Ok(mirror_version) if mirror_version != new_version => warnings.push(format!(
+                "Upstream mirrors disagree ('{}' vs '{}' from {})",
+                new_version, mirror_version, mirror_url
+            )),
+            Ok(_) => (),
+            Err(e) => warnings.push(format!("Failed to check upstream mirror: {}", e)),
+        }
+    }
     let new_version = if comply {
         let new_version_before_modification = new_version;
         let complied = new_version.compily_with_aosc();
@@ -173,13 +794,43 @@ fn check_update_worker<P: AsRef<Path>>(
         new_version.to_string()
     };
     let new_version = new_version.as_str();
+    debug!("chosen version: {}", new_version);
+    if let Some(format) = &options.version_format {
+        validate_version_format(new_version, format)?;
+    }
     let name = normalize_name(spec.as_ref()).to_string();
     if current_version == new_version {
         return Ok(CheckerResult {
             name,
+            checker_type,
             warnings,
             before: current_version.to_string(),
             after: new_version.to_string(),
+            raw_upstream,
+            candidates_considered,
+            change: None,
+            path: None,
+        });
+    }
+    if is_same_version(current_version, new_version) {
+        info!(
+            "{}: no semantic change from '{}' to '{}', not updating",
+            name, current_version, new_version
+        );
+        warnings.push(format!(
+            "No semantic change from '{}' to '{}'",
+            current_version, new_version
+        ));
+        return Ok(CheckerResult {
+            name,
+            checker_type,
+            warnings,
+            before: current_version.to_string(),
+            after: current_version.to_string(),
+            raw_upstream,
+            candidates_considered,
+            change: None,
+            path: None,
         });
     }
     let snapshot_version = AhoCorasickBuilder::new().build(VCS_VERSION_NUMBERS);
@@ -192,102 +843,449 @@ fn check_update_worker<P: AsRef<Path>>(
             ))
         }
     }
-    if let Ok(ret) = compare_to(current_version, new_version, Cmp::Gt) {
-        if ret {
-            warnings.push(format!(
-                "Possible downgrade from the current version ({} -> {})",
-                current_version, new_version
-            ));
-        }
+    if let Some(warning) =
+        downgrade_warning(current_version, new_version, &raw_upstream, is_upstream_ver)
+    {
+        warnings.push(warning);
+    }
+    if let Some(warning) = version_jump_warning(
+        current_version,
+        if is_upstream_ver {
+            &raw_upstream
+        } else {
+            new_version
+        },
+    ) {
+        warnings.push(warning);
+    }
+    if options.only_upgrades {
+        validate_upgrade(current_version, new_version)?;
+    }
+
+    let rel = if s.get("CHKUPDATE_KEEP_REL").map(|v| v.trim()) == Some("1") {
+        RelAction::Keep
     } else {
-        warnings.push(format!(
-            "Versions not comparable: `{}` and `{}`",
-            current_version, new_version
-        ));
+        options.rel
+    };
+    let (original, modified) = if options.dry_run {
+        let original = std::fs::read_to_string(spec.as_ref())?;
+        let modified = replace_version(&original, new_version, is_upstream_ver, rel);
+        (original, modified)
+    } else {
+        let _guard = options.write_lock.map(|l| l.lock().unwrap());
+        update_version(
+            new_version,
+            spec.as_ref(),
+            is_upstream_ver,
+            rel,
+            options.backup,
+        )?
+    };
+    if options.explain {
+        debug!("spec would be updated to:\n{}", modified);
     }
 
-    if !dry_run {
-        let modified = update_version(new_version, spec.as_ref(), is_upstream_ver)?;
+    let mut reverted = false;
+    if !options.dry_run {
+        // the fast path doesn't read the sources, so evaluate the original spec as well
+        let old_ctx = parser::evaluate_spec(&original).unwrap_or_default();
         let mut new_ctx = HashMap::new();
-        match abbs_meta_apml::parse(&modified, &mut new_ctx) {
-            Ok(_) => {
-                if validate_urls(&s, &new_ctx) {
-                    warnings.push("Hardcoded URLs detected.".to_string());
-                }
+        let errors = {
+            let _guard = options.write_lock.map(|l| l.lock().unwrap());
+            reparse_or_revert(spec.as_ref(), &original, &modified, &mut new_ctx)?
+        };
+        if errors.is_empty() {
+            if validate_urls(&old_ctx, &new_ctx) {
+                warnings.push("Hardcoded URLs detected.".to_string());
             }
-            Err(err) => {
-                for i in err {
-                    warnings.push(format!("Modified spec is broken: {i}"));
-                }
+        } else {
+            reverted = true;
+            for i in errors {
+                warnings.push(format!("Modified spec is broken, update reverted: {i}"));
             }
         }
     }
 
     Ok(CheckerResult {
         name,
+        checker_type,
         warnings,
         before: current_version.to_string(),
         after: new_version.to_string(),
+        raw_upstream,
+        candidates_considered,
+        change: (!reverted).then_some(SpecChange {
+            before: original,
+            after: modified,
+        }),
+        path: None,
     })
 }
 
-fn print_results(results: &[Result<CheckerResult>], version_only: bool) {
+fn print_diffstat(results: &[Result<CheckerResult>]) {
+    let (mut changed, mut added, mut removed) = (0, 0, 0);
+    for change in results.iter().flatten().filter_map(|x| x.change.as_ref()) {
+        let (a, r) = diff::diffstat(&change.before, &change.after);
+        changed += 1;
+        added += a;
+        removed += r;
+    }
+    println!(
+        "\n{} specs changed, {} insertions(+), {} deletions(-)",
+        changed, added, removed
+    );
+}
+
+/// Formats the changes made to the specs as unified diffs, ordered by package path
+fn format_diffs(results: &[Result<CheckerResult>]) -> String {
+    let mut changes = results
+        .iter()
+        .flatten()
+        .filter_map(|x| Some((x.path.as_ref().unwrap_or(&x.name), x.change.as_ref()?)))
+        .collect::<Vec<_>>();
+    changes.sort_unstable_by_key(|(path, _)| *path);
+
+    changes
+        .into_iter()
+        .map(|(path, change)| {
+            diff::git_patch(&format!("{}/spec", path), &change.before, &change.after)
+        })
+        .collect()
+}
+
+fn print_diffs(results: &[Result<CheckerResult>]) {
+    println!();
+    for line in format_diffs(results).lines() {
+        let style =
+            if line.starts_with("diff ") || line.starts_with("--- ") || line.starts_with("+++ ") {
+                Style::new().bold()
+            } else if line.starts_with("@@") {
+                Style::new().cyan()
+            } else if line.starts_with('+') {
+                Style::new().green()
+            } else if line.starts_with('-') {
+                Style::new().red()
+            } else {
+                Style::new()
+            };
+        println!("{}", paint(line, style));
+    }
+}
+
+/// Counts of the outcomes of a run
+#[derive(Debug, Default, PartialEq)]
+struct RunSummary {
+    checked: usize,
+    updated: usize,
+    errors: usize,
+    warnings: usize,
+}
+
+impl RunSummary {
+    fn from_results(results: &[Result<CheckerResult>]) -> Self {
+        let mut summary = RunSummary {
+            checked: results.len(),
+            ..Default::default()
+        };
+        for result in results {
+            match result {
+                Ok(result) => {
+                    if result.before != result.after {
+                        summary.updated += 1;
+                    }
+                    summary.warnings += result.warnings.len();
+                }
+                Err(_) => summary.errors += 1,
+            }
+        }
+
+        summary
+    }
+}
+
+impl Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} checked, {} updated, {} errors, {} warnings",
+            self.checked, self.updated, self.errors, self.warnings
+        )
+    }
+}
+
+/// Number of packages listed by `--timings`
+const SLOWEST_SHOWN: usize = 10;
+
+/// Lists the packages that took the longest to check, slowest first
+fn format_slowest(timings: &[(String, Duration)], count: usize) -> String {
+    let mut slowest = timings.iter().collect::<Vec<_>>();
+    slowest.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let mut out = format!("Slowest {} packages:\n", count.min(slowest.len()));
+    for (name, duration) in slowest.into_iter().take(count) {
+        out.push_str(&format!("{:>8} ms  {}\n", duration.as_millis(), name));
+    }
+
+    out
+}
+
+/// Format of the results printed to stdout
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Table,
+    Csv,
+    Tsv,
+}
+
+impl OutputFormat {
+    /// Formats a row of a CSV or TSV output
+    fn row(&self, fields: &[&str]) -> String {
+        match self {
+            OutputFormat::Tsv => fields
+                .iter()
+                .map(|f| f.replace(['\t', '\n', '\r'], " "))
+                .collect::<Vec<_>>()
+                .join("\t"),
+            _ => fields
+                .iter()
+                .map(|f| {
+                    if f.contains([',', ';', '"', '\n', '\r']) {
+                        format!("\"{}\"", f.replace('"', "\"\""))
+                    } else {
+                        f.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+fn print_results(
+    results: &[Result<CheckerResult>],
+    format: OutputFormat,
+    version_only: bool,
+    show_raw: bool,
+    show_path: bool,
+) {
     if version_only {
         for result in results.iter().flatten() {
             println!("{}", result.after);
         }
+    } else if format != OutputFormat::Table {
+        println!("{}", format.row(&["name", "before", "after", "warnings"]));
+        for result in results.iter().flatten() {
+            if result.before == result.after {
+                continue;
+            }
+            println!(
+                "{}",
+                format.row(&[
+                    &result.name,
+                    &result.before,
+                    &result.after,
+                    &result.warnings.join("; ")
+                ])
+            );
+        }
+        // keep stdout parsable
+        for result in results {
+            if let Err(e) = result {
+                eprintln!("{}", e);
+            }
+        }
     } else {
         println!("The following packages were updated:");
-        println!("{:<30}{:^44}\t\tIssues", "Name", "Version");
+        let mut header = format!("{:<30}{:^44}", "Name", "Version");
+        if show_raw {
+            header.push_str(&format!("{:<20}", "Upstream"));
+        }
+        if show_path {
+            header.push_str(&format!("{:<40}", "Path"));
+        }
+        println!("{}\t\tIssues", header);
         for result in results.iter().flatten() {
             if result.before == result.after {
                 continue;
             }
+            let mut line = format!(
+                "{:<30}{:>20} -> {:<20}",
+                paint(&result.name, Style::new().cyan()),
+                paint(&result.before, Style::new().red()),
+                paint(&result.after, Style::new().green())
+            );
+            if show_raw {
+                line.push_str(&format!("{:<20}", result.raw_upstream));
+            }
+            if show_path {
+                let path = result.path.as_deref().unwrap_or_default();
+                line.push_str(&format!("{:<40}", path));
+            }
             println!(
-                "{:<30}{:>20} -> {:<20}\t\t{}",
-                result.name.cyan(),
-                result.before.red(),
-                result.after.green(),
-                result.warnings.join("; ").yellow()
+                "{}\t\t{}",
+                line,
+                paint(result.warnings.join("; "), Style::new().yellow())
             );
         }
         println!("\nErrors:");
         for result in results {
             if let Err(e) = result {
-                println!("{}", e.bold());
+                println!("{}", paint(e, Style::new().bold()));
             }
         }
     }
 }
 
+/// Overall outcome of a run, reported as the exit status with `--strict`
+#[derive(Debug, PartialEq)]
+enum RunOutcome {
+    UpToDate,
+    UpdatesFound,
+    Errors,
+}
+
+impl RunOutcome {
+    /// Errors take precedence over updates, as the results are incomplete
+    fn from_results(results: &[Result<CheckerResult>]) -> Self {
+        if results.iter().any(|r| r.is_err()) {
+            RunOutcome::Errors
+        } else if results.iter().flatten().any(|r| r.before != r.after) {
+            RunOutcome::UpdatesFound
+        } else {
+            RunOutcome::UpToDate
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        match self {
+            RunOutcome::UpToDate => 0,
+            RunOutcome::UpdatesFound => 2,
+            RunOutcome::Errors => 3,
+        }
+    }
+}
+
+/// Stops checking after the first failed check with `--fail-fast`
+#[derive(Debug, Default)]
+struct FailFast {
+    enabled: bool,
+    /// Index of the first failed check
+    first_error: OnceLock<usize>,
+    /// Number of checks skipped after it
+    skipped: AtomicUsize,
+}
+
+impl FailFast {
+    fn new(enabled: bool) -> Self {
+        FailFast {
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the check should be skipped, as an earlier one failed
+    fn skip(&self) -> bool {
+        if self.first_error.get().is_none() {
+            return false;
+        }
+        self.skipped.fetch_add(1, Ordering::SeqCst);
+
+        true
+    }
+
+    fn record<T>(&self, index: usize, result: &Result<T>) {
+        if self.enabled && result.is_err() {
+            self.first_error.set(index).ok();
+        }
+    }
+}
+
+/// Runs `f` in the given thread pool, or in the global one if there is none
+fn in_pool<R: Send>(pool: Option<&ThreadPool>, f: impl FnOnce() -> R + Send) -> R {
+    match pool {
+        Some(pool) => pool.install(f),
+        None => f(),
+    }
+}
+
 fn main() {
     let args = cli::build_cli().get_matches();
-    env_logger::init();
+    if args.get_flag("PRINT_SCHEMA") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output_schema()).unwrap()
+        );
+        return;
+    }
+    let explain = args.get_one::<String>("EXPLAIN");
+    let color_choice = args.get_one::<String>("COLOR").unwrap();
+    let color = use_color(
+        color_choice,
+        std::io::stdout().is_terminal(),
+        std::env::var("NO_COLOR").ok().as_deref(),
+        std::env::var("TERM").ok().as_deref(),
+    );
+    COLOR.store(color, Ordering::Relaxed);
+    let mut logger = env_logger::Builder::from_default_env();
+    // in auto mode, the logger checks stderr and NO_COLOR by itself
+    match color_choice.as_str() {
+        "always" => logger.write_style(env_logger::WriteStyle::Always),
+        "never" => logger.write_style(env_logger::WriteStyle::Never),
+        _ => &mut logger,
+    };
+    if explain.is_some() {
+        logger.filter_module("aosc_findupdate", LevelFilter::Debug);
+    }
+    logger.init();
     let mut pattern = None;
     if let Some(p) = args.get_one::<String>("INCLUDE") {
         pattern = Some(Regex::new(p).unwrap());
     }
-    let dry_run = args.get_flag("DRY_RUN");
+    let exclude = args.get_one::<Regex>("EXCLUDE").cloned();
+    let dry_run = args.get_flag("DRY_RUN") || explain.is_some();
     let comply_with_aosc = args.get_flag("COMPLY");
     let version_only = args.get_flag("VERSION_ONLY");
     let update_checksum = args.get_flag("UPDATE_CHECKSUM");
+    let skip_checksum_on_warning = args.get_flag("SKIP_CHECKSUM_ON_WARNING");
+    let dedup = args.get_flag("DEDUP");
+    let full_parse = args.get_flag("FULL_PARSE");
+    let template = args.get_one::<String>("TEMPLATE").map(|f| {
+        let template = std::fs::read_to_string(f).expect("Failed to read the template.");
+        if let Err(e) = template::validate(&template) {
+            eprintln!("Invalid template {}: {}", f, e);
+            std::process::exit(1);
+        }
+        template
+    });
+    let version_format = args.get_one::<Regex>("STRICT_VERSION_FORMAT").cloned();
+    let skip_list = args.get_one::<String>("SKIP_FILE").map(|f| {
+        parser::read_skip_list(f).unwrap_or_else(|e| {
+            eprintln!("Unable to read the skip list {}: {}", f, e);
+            std::process::exit(1);
+        })
+    });
     let current_path = std::env::current_dir().expect("Failed to get current dir.");
-    let workdir = if let Some(d) = args.get_one::<String>("DIR") {
-        Path::new(d).canonicalize().unwrap()
-    } else {
-        Path::new(".").canonicalize().unwrap()
-    };
+    let workdirs = args
+        .get_many::<String>("DIR")
+        .map(|dirs| dirs.map(|d| Path::new(d).canonicalize().unwrap()).collect())
+        .unwrap_or_else(|| vec![Path::new(".").canonicalize().unwrap()]);
 
     let mut files = if let Some(list) = args.get_one::<String>("FILE") {
         let path = Path::new(list).canonicalize().unwrap();
-        std::env::set_current_dir(workdir).expect("Failed to set current directory");
+        std::env::set_current_dir(&workdirs[0]).expect("Failed to set current directory");
         let list = parser::expand_package_list([&path]);
-        list.into_iter()
-            .map(|x| Path::new(&x).join("spec"))
-            .collect()
+        specs_in_trees(&list, &workdirs)
     } else {
-        std::env::set_current_dir(workdir).expect("Failed to set current directory");
-        collect_spec(Path::new(".")).unwrap()
+        std::env::set_current_dir(&workdirs[0]).expect("Failed to set current directory");
+        let modified_since = args.get_one::<u64>("MODIFIED_WITHIN").map(|days| {
+            SystemTime::now()
+                .checked_sub(Duration::from_secs(days * 24 * 60 * 60))
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+        });
+        let mut files = vec![];
+        for workdir in &workdirs {
+            files.extend(collect_spec(workdir, modified_since).unwrap());
+        }
+        files
     };
 
     if let Some(pattern) = pattern {
@@ -300,25 +1298,229 @@ fn main() {
         });
     }
 
+    if let Some(exclude) = exclude {
+        files.retain(|x| {
+            if let Some(name) = x.parent().map(|p| p.to_string_lossy()) {
+                !exclude.is_match(&name)
+            } else {
+                true
+            }
+        });
+    }
+
+    if let Some(skip_list) = skip_list {
+        files.retain(|x| !skip_list.contains(normalize_name(x).as_ref()));
+    }
+
+    if let Some(scheme) = args.get_one::<String>("SCHEME") {
+        let scheme: VersioningType = scheme.parse().unwrap();
+        files = files
+            .into_par_iter()
+            .filter(|f| parser::parse_spec(f, full_parse).is_ok_and(|s| matches_scheme(&s, scheme)))
+            .collect();
+    }
+
+    if let Some(explain) = explain {
+        files.retain(|x| normalize_name(x) == explain.as_str());
+        if files.is_empty() {
+            eprintln!("Cannot find package {} in the tree.", explain);
+            std::process::exit(1);
+        }
+    }
+
+    if args.get_flag("WARN_DUPLICATE_NAMES") {
+        warn_duplicate_names(&trees_of(&workdirs)).expect("Failed to walk the tree.");
+    }
+
     if dry_run {
         warn!("Dry-run mode: files will not be updated.");
     }
     let total = files.len();
     info!("Checking updates for {} packages ...", total);
+    let user_agent = args
+        .get_one::<String>("USER_AGENT")
+        .cloned()
+        .or_else(|| std::env::var("FINDUPDATE_USER_AGENT").ok())
+        .filter(|ua| !ua.is_empty());
+    if let Some(ua) = user_agent.as_deref() {
+        if HeaderValue::from_str(ua).is_err() {
+            eprintln!("Invalid User-Agent: {:?}", ua);
+            std::process::exit(1);
+        }
+    }
+    checker::set_request_options(checker::RequestOptions {
+        require_https: args.get_flag("REQUIRE_HTTPS"),
+        insecure_hosts: args
+            .get_many::<String>("ALLOW_INSECURE_HOST")
+            .map(|hosts| hosts.cloned().collect())
+            .unwrap_or_default(),
+        rate_limit: args
+            .get_one::<f64>("RATE")
+            .map(|rate| checker::RateLimiter::new(*rate, args.get_flag("RATE_HOST"))),
+        host_limit: args
+            .get_one::<u64>("PER_HOST")
+            .map(|max| checker::HostLimiter::new(*max as usize)),
+        retries: *args.get_one::<u32>("RETRIES").unwrap(),
+        max_body: args.get_one::<u64>("MAX_BODY").copied(),
+        user_agent: user_agent.clone(),
+        git_version: args.get_one::<String>("GIT_VERSION").cloned(),
+    });
+    checker::set_default_sort(args.get_flag("DEFAULT_SORT"));
+    checker::set_candidate_limit(checker::CandidateLimit {
+        max: *args.get_one::<u64>("MAX_CANDIDATES").unwrap() as usize,
+        action: match args
+            .get_one::<String>("MAX_CANDIDATES_ACTION")
+            .map(|s| s.as_str())
+        {
+            Some("error") => checker::CandidateLimitAction::Error,
+            _ => checker::CandidateLimitAction::Truncate,
+        },
+    });
+    let clients = ClientOptions::new(
+        Duration::from_secs(*args.get_one::<u64>("TIMEOUT").unwrap()),
+        args.get_flag("DNS_CACHE"),
+        user_agent,
+    );
+    let pool = args.get_one::<u64>("JOBS").map(|jobs| {
+        ThreadPoolBuilder::new()
+            .num_threads(*jobs as usize)
+            .build()
+            .expect("Failed to create the thread pool.")
+    });
+    if args.get_flag("CHECK_URLS_ONLY") {
+        in_pool(pool.as_ref(), || {
+            check_url_health(&files, full_parse, &clients)
+        });
+        return;
+    }
+    let cache = if dedup {
+        Some(in_pool(pool.as_ref(), || {
+            check_unique_upstreams(&files, full_parse, &clients)
+        }))
+    } else {
+        None
+    };
+    let serial_writes = args.get_flag("SERIAL_WRITES");
+    let write_lock = Mutex::new(());
+    let options = WorkerOptions {
+        dry_run,
+        comply: comply_with_aosc,
+        explain: explain.is_some(),
+        rel: if args.get_flag("KEEP_REL") {
+            RelAction::Keep
+        } else if args.get_flag("RESET_REL") {
+            RelAction::Reset
+        } else {
+            RelAction::Remove
+        },
+        backup: args.get_flag("BACKUP"),
+        only_upgrades: args.get_flag("ONLY_UPGRADES"),
+        full_parse,
+        cache: cache.as_ref(),
+        version_format,
+        write_lock: serial_writes.then_some(&write_lock),
+    };
+    let checked_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
     let current = Arc::new(AtomicUsize::new(1));
 
-    let results: Vec<_> = files
-        .par_iter()
-        .map_init(Client::new, |c, f| {
-            let name = normalize_name(f);
-            let current = current.fetch_add(1, Ordering::SeqCst);
-            info!("[{}/{}] Checking {} ...", current, total, &name);
-            check_update_worker(c, f, dry_run, comply_with_aosc)
-                .map_err(|e| anyhow!("{}: {:?}", name.cyan(), e))
-        })
-        .collect();
+    let fail_fast = FailFast::new(args.get_flag("FAIL_FAST"));
+    let timed: Vec<_> = in_pool(pool.as_ref(), || {
+        files
+            .par_iter()
+            .enumerate()
+            .map_init(
+                || clients.worker_client(),
+                |c, (i, f)| {
+                    let name = normalize_name(f);
+                    if fail_fast.skip() {
+                        return (Err(anyhow!("{}: skipped", name)), Duration::ZERO);
+                    }
+                    let current = current.fetch_add(1, Ordering::SeqCst);
+                    info!("[{}/{}] Checking {} ...", current, total, &name);
+                    let start = Instant::now();
+                    let result = check_update_worker(c, f, &options).map_err(|e| {
+                        if is_timeout(&e) {
+                            anyhow!(
+                                "{}: timed out after {} seconds: {:?}",
+                                paint(&name, Style::new().cyan()),
+                                clients.timeout.as_secs(),
+                                e
+                            )
+                        } else {
+                            anyhow!("{}: {:?}", paint(&name, Style::new().cyan()), e)
+                        }
+                    });
+                    fail_fast.record(i, &result);
 
-    print_results(&results, version_only);
+                    (result, start.elapsed())
+                },
+            )
+            .collect()
+    });
+    let (mut results, durations): (Vec<_>, Vec<_>) = timed.into_iter().unzip();
+    if let Some(Err(e)) = fail_fast.first_error.get().map(|&i| &results[i]) {
+        eprintln!("{}", e);
+        eprintln!(
+            "Aborted on the first error (--fail-fast), {} packages were not checked.",
+            fail_fast.skipped.load(Ordering::SeqCst)
+        );
+        std::process::exit(RunOutcome::Errors.exit_code());
+    }
+    let timings = args.get_flag("TIMINGS").then(|| {
+        files
+            .iter()
+            .map(|f| normalize_name(f).to_string())
+            .zip(durations.iter().copied())
+            .collect::<Vec<_>>()
+    });
+
+    let show_path = args.get_flag("SHOW_PATH");
+    let log = args.get_one::<String>("LOG");
+    let json = args.get_one::<String>("JSON");
+    let template_out = args.get_one::<String>("TEMPLATE_OUT");
+    let patch_out = args.get_one::<String>("PATCH_OUT");
+    let show_diff = args.get_flag("DIFF") && !version_only;
+    // resolving paths walks up to the tree of each spec, so only do it when needed
+    if show_path
+        || show_diff
+        || log.is_some()
+        || json.is_some()
+        || template_out.is_some()
+        || patch_out.is_some()
+    {
+        let with_tree = trees_of(&workdirs).len() > 1;
+        resolve_paths(&mut results, &files, with_tree);
+    }
+
+    // `--json -` writes the results to stdout, which must not be mixed with the table
+    let json_stdout = json.is_some_and(|j| j == "-");
+    if !json_stdout {
+        print_results(
+            &results,
+            match args.get_one::<String>("FORMAT").map(|s| s.as_str()) {
+                Some("csv") => OutputFormat::Csv,
+                Some("tsv") => OutputFormat::Tsv,
+                _ => OutputFormat::Table,
+            },
+            version_only,
+            comply_with_aosc,
+            show_path,
+        );
+        if show_diff {
+            print_diffs(&results);
+        }
+        if args.get_flag("DIFFSTAT") && !version_only {
+            print_diffstat(&results);
+        }
+    }
+
+    // on stderr, so that it doesn't end up in piped output
+    if !version_only {
+        eprintln!("\n{}", RunSummary::from_results(&results));
+    }
+    if let Some(timings) = &timings {
+        eprint!("\n{}", format_slowest(timings, SLOWEST_SHOWN));
+    }
 
     if update_checksum {
         // Update checksum via `acbs-build -gw`
@@ -328,50 +1530,76 @@ fn main() {
             if result.before == result.after {
                 continue;
             }
+            if skip_checksum_on_warning {
+                if let Some(warning) = result
+                    .warnings
+                    .iter()
+                    .find(|w| BLOCKING_WARNINGS.iter().any(|b| w.starts_with(b)))
+                {
+                    println!("Skipping checksum update for {}: {}", result.name, warning);
+                    continue;
+                }
+            }
             packages.push(result.name.as_str());
         }
-        let arg = packages.join(" ");
+        let batch_size = *args.get_one::<u64>("BATCH_SIZE").unwrap() as usize;
+        let batches = packages.chunks(batch_size).collect::<Vec<_>>();
+        let mut failed = vec![];
+        for (i, batch) in batches.iter().enumerate() {
+            let arg = batch.join(" ");
 
-        // add -E to pass CIEL_INST environment variable
-        println!(
-            "Updating checksum via: sudo -E ciel shell -- acbs-build -gw {}",
-            arg
-        );
-        if !dry_run {
-            if let Err(err) = Command::new("sudo")
+            // add -E to pass CIEL_INST environment variable
+            println!(
+                "Updating checksum ({}/{}) via: sudo -E ciel shell -- acbs-build -gw {}",
+                i + 1,
+                batches.len(),
+                arg
+            );
+            if dry_run {
+                continue;
+            }
+            match Command::new("sudo")
                 .args(["-E", "ciel", "shell", "--", "acbs-build", "-gw", &arg])
                 .status()
             {
-                println!("Failed with {}", err);
+                Ok(status) if status.success() => (),
+                Ok(status) => {
+                    println!("Batch {}/{} failed with {}", i + 1, batches.len(), status);
+                    failed.push(arg);
+                }
+                Err(err) => {
+                    println!("Batch {}/{} failed with {}", i + 1, batches.len(), err);
+                    failed.push(arg);
+                }
+            }
+        }
+        if !failed.is_empty() {
+            println!("Failed to update checksum for:");
+            for batch in failed {
+                println!("  {}", batch);
             }
         }
     }
 
-    let log = args.get_one::<String>("LOG");
-    let json = args.get_one::<String>("JSON");
     if log.is_some() || json.is_some() {
-        let tree = get_tree(Path::new(".")).expect("Failed to get tree path.");
-
-        let items = results
-            .par_iter()
-            .filter_map(|x| {
-                if let Ok(ret) = x {
-                    if ret.after == ret.before {
-                        return None;
-                    }
-
-                    Some(CheckResultOutput {
-                        name: ret.name.to_owned(),
-                        before: ret.before.to_owned(),
-                        after: ret.after.to_owned(),
-                        path: find_path(&ret.name, &tree),
-                        warnings: ret.warnings.to_vec(),
-                    })
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
+        let items = || {
+            results
+                .iter()
+                .zip(&durations)
+                .filter_map(|(ret, duration)| Some((ret.as_ref().ok()?, duration)))
+                .filter(|(ret, _)| ret.after != ret.before)
+                .map(|(ret, duration)| CheckResultOutput {
+                    name: &ret.name,
+                    before: &ret.before,
+                    after: &ret.after,
+                    raw_upstream: &ret.raw_upstream,
+                    candidates_considered: ret.candidates_considered,
+                    path: ret.path.as_deref().unwrap(),
+                    warnings: &ret.warnings,
+                    checked_at: &checked_at,
+                    duration_ms: timings.is_some().then_some(duration.as_millis() as u64),
+                })
+        };
 
         if let Some(log) = log {
             let log = Path::new(log);
@@ -382,8 +1610,8 @@ fn main() {
             };
 
             let mut f = File::create(&*log).unwrap();
-            for i in &items {
-                writeln!(f, "{}", find_path(&i.name, &tree)).unwrap();
+            for i in items() {
+                writeln!(f, "{}", i.path).unwrap();
             }
 
             info!("Wrote results to {}", log.display());
@@ -391,16 +1619,99 @@ fn main() {
 
         if let Some(json) = json {
             let json = Path::new(json);
-            let json = if json.is_absolute() {
+            let json = if json.is_absolute() || json_stdout {
                 Cow::Borrowed(json)
             } else {
                 Cow::Owned(current_path.join(json))
             };
 
-            let mut f = File::create(&*json).unwrap();
-            serde_json::to_writer(&mut f, &items).unwrap();
-            info!("Wrote results to {}", json.display());
+            let mut f: BufWriter<Box<dyn Write>> = if json_stdout {
+                BufWriter::new(Box::new(std::io::stdout().lock()))
+            } else {
+                BufWriter::new(Box::new(File::create(&*json).unwrap()))
+            };
+            if args.get_flag("JSON_V2") {
+                let output = JsonOutputV2 {
+                    generated_at: &Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                    results: JsonStream::new(items()),
+                };
+                serde_json::to_writer(&mut f, &output).unwrap();
+            } else {
+                serde_json::to_writer(&mut f, &JsonStream::new(items())).unwrap();
+            }
+            if json_stdout {
+                writeln!(f).unwrap();
+            }
+            f.flush().unwrap();
+            if !json_stdout {
+                info!("Wrote results to {}", json.display());
+            }
+        }
+    }
+
+    if let (Some(out), Some(template)) = (template_out, &template) {
+        let out = Path::new(out);
+        let out = if out.is_absolute() {
+            Cow::Borrowed(out)
+        } else {
+            Cow::Owned(current_path.join(out))
+        };
+
+        let mut f = BufWriter::new(File::create(&*out).unwrap());
+        for ret in results
+            .iter()
+            .flatten()
+            .filter(|ret| ret.after != ret.before)
+        {
+            let values = HashMap::from([
+                ("name", ret.name.as_str()),
+                ("before", ret.before.as_str()),
+                ("after", ret.after.as_str()),
+                ("path", ret.path.as_deref().unwrap()),
+                ("type", ret.checker_type.as_str()),
+            ]);
+            // the template was validated before checking
+            let rendered = template::render(template, &values).unwrap();
+            f.write_all(rendered.as_bytes()).unwrap();
         }
+        f.flush().unwrap();
+        info!("Wrote results to {}", out.display());
+    }
+
+    if let Some(out) = patch_out {
+        let out = Path::new(out);
+        let out = if out.is_absolute() {
+            Cow::Borrowed(out)
+        } else {
+            Cow::Owned(current_path.join(out))
+        };
+
+        let mut f = BufWriter::new(File::create(&*out).unwrap());
+        for ret in results
+            .iter()
+            .flatten()
+            .filter(|ret| ret.after != ret.before)
+        {
+            if let Some(change) = &ret.change {
+                let path = format!("{}/spec", ret.path.as_deref().unwrap());
+                let patch = diff::git_patch(&path, &change.before, &change.after);
+                f.write_all(patch.as_bytes()).unwrap();
+            }
+        }
+        f.flush().unwrap();
+        info!("Wrote the patch to {}", out.display());
+    }
+
+    if args.get_flag("FAIL_ON_NO_UPDATE") && !results.iter().flatten().any(|r| r.before != r.after)
+    {
+        eprintln!("No update was found.");
+        std::process::exit(1);
+    }
+
+    if args.get_flag("STRICT") {
+        let outcome = RunOutcome::from_results(&results);
+        debug!("run outcome: {:?}", outcome);
+        std::process::exit(outcome.exit_code());
     }
 }
 
@@ -419,30 +1730,747 @@ fn get_tree(directory: &Path) -> Result<PathBuf> {
     }
 }
 
-fn find_path(pkg: &str, tree: &Path) -> String {
-    let path = find_path_inner(pkg, tree).expect(&format!("Failed to find path: {}", pkg));
+/// Returns the trees containing the given directories, without duplicates
+fn trees_of(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut trees = dirs
+        .iter()
+        .map(|d| get_tree(d).expect("Failed to get tree path."))
+        .collect::<Vec<_>>();
+    trees.sort();
+    trees.dedup();
 
-    let path = path
-        .strip_prefix(&tree)
-        .expect(&format!("Failed to strip prefix path: {}", tree.display()));
+    trees
+}
 
-    path.display().to_string()
+/// Returns the path of the package relative to the tree containing its spec (e.g.
+/// `app-utils/foo`), or the full path if `with_tree` is set, so that the same package in several
+/// trees can be told apart
+fn find_path(spec: &Path, with_tree: bool) -> Result<String> {
+    let dir = spec.parent().unwrap_or(spec).canonicalize()?;
+    let tree = get_tree(&dir)?;
+    let path = if with_tree {
+        &dir
+    } else {
+        dir.strip_prefix(&tree)?
+    };
+
+    Ok(path.display().to_string())
 }
 
-fn find_path_inner(name: &str, tree: &Path) -> Result<PathBuf> {
-    let packages = WalkDir::new(tree).min_depth(2).max_depth(2);
-    let mut path = None;
+/// Resolves the paths of the updated packages, `results` being in the same order as `specs`
+fn resolve_paths(results: &mut [Result<CheckerResult>], specs: &[PathBuf], with_tree: bool) {
+    results
+        .par_iter_mut()
+        .zip(specs.par_iter())
+        .filter_map(|(ret, spec)| Some((ret.as_mut().ok()?, spec)))
+        .filter(|(ret, _)| ret.after != ret.before)
+        .for_each(|(ret, spec)| {
+            let path = find_path(spec, with_tree)
+                .unwrap_or_else(|e| panic!("Failed to find path of {}: {}", ret.name, e));
+            ret.path = Some(path);
+        });
+}
+
+/// Resolves the packages of a `-f` list to their specs. With several trees, a package is checked
+/// in each tree that has it, and in the first tree if none does (to report it as missing)
+fn specs_in_trees(packages: &[String], trees: &[PathBuf]) -> Vec<PathBuf> {
+    let mut specs = vec![];
+    for package in packages {
+        let found = trees
+            .iter()
+            .map(|tree| tree.join(package).join("spec"))
+            .filter(|spec| trees.len() == 1 || spec.is_file())
+            .collect::<Vec<_>>();
+        if found.is_empty() {
+            specs.push(trees[0].join(package).join("spec"));
+        } else {
+            specs.extend(found);
+        }
+    }
+
+    specs
+}
 
-    for entry in packages {
-        let entry = entry?;
-        let p = entry.into_path().canonicalize()?;
-        let file_name = normalize_filename(&p);
+/// Groups the package directories by name, keeping the names shared by several of them
+fn duplicate_names(dirs: impl IntoIterator<Item = PathBuf>) -> BTreeMap<String, Vec<PathBuf>> {
+    let mut names: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for dir in dirs {
+        names
+            .entry(normalize_filename(&dir).to_string())
+            .or_default()
+            .push(dir);
+    }
+    names.retain(|_, paths| paths.len() > 1);
+
+    names
+}
 
-        if file_name == name {
-            path = Some(p);
-            break;
+/// Warns about the packages sharing a name across sections (or trees), whose paths would be
+/// ambiguous
+fn warn_duplicate_names(trees: &[PathBuf]) -> Result<()> {
+    let mut dirs = vec![];
+    for tree in trees {
+        for entry in WalkDir::new(tree).min_depth(2).max_depth(2) {
+            let entry = entry?;
+            if entry.file_type().is_dir() && entry.path().join("spec").is_file() {
+                dirs.push(entry.into_path());
+            }
         }
     }
+    for (name, paths) in duplicate_names(dirs) {
+        // the paths are only shown relative to the tree if there is no other tree
+        let paths = paths
+            .iter()
+            .map(|p| match trees {
+                [tree] => p.strip_prefix(tree).unwrap_or(p).display().to_string(),
+                _ => p.display().to_string(),
+            })
+            .collect::<Vec<_>>();
+        warn!(
+            "Package name {} is used by several directories: {}",
+            name,
+            paths.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_replace_version() {
+    let spec = "VER=1.0\nREL=1\nSRCS=\"tbl::https://example.com/foo-$VER.tar.gz\"\n";
+    assert_eq!(
+        replace_version(spec, "1.1", false, RelAction::Remove),
+        "VER=1.1\nSRCS=\"tbl::https://example.com/foo-$VER.tar.gz\"\n"
+    );
+    let spec = "#VER=0.9\nMYVER=2.0\nVER=1.0\n";
+    assert_eq!(
+        replace_version(spec, "1.1", false, RelAction::Remove),
+        "#VER=0.9\nMYVER=2.0\nVER=1.1\n"
+    );
+    let spec = "VER=1.0\nMYREL=2\nREL=1\n";
+    assert_eq!(
+        replace_version(spec, "1.1", false, RelAction::Remove),
+        "VER=1.1\nMYREL=2\n"
+    );
+    let spec = "VER=1.0+git20230101\nUPSTREAM_VER=1.0\n";
+    assert_eq!(
+        replace_version(spec, "1.1", true, RelAction::Remove),
+        "VER=1.0+git20230101\nUPSTREAM_VER=1.1\n"
+    );
+}
+
+#[test]
+fn test_replace_version_keep_rel() {
+    let spec = "VER=1.0\nREL=3\n";
+    assert_eq!(
+        replace_version(spec, "1.1", false, RelAction::Keep),
+        "VER=1.1\nREL=3\n"
+    );
+    assert_eq!(
+        replace_version(spec, "1.1", false, RelAction::Remove),
+        "VER=1.1\n"
+    );
+}
+
+#[test]
+fn test_replace_version_reset_rel() {
+    let spec = "VER=1.0\nREL=3\nSRCS=\"tbl::https://example.com/foo-$VER.tar.gz\"\n";
+    assert_eq!(
+        replace_version(spec, "1.1", false, RelAction::Reset),
+        "VER=1.1\nREL=0\nSRCS=\"tbl::https://example.com/foo-$VER.tar.gz\"\n"
+    );
+    // unchanged version, the release is left alone
+    assert_eq!(replace_version(spec, "1.0", false, RelAction::Reset), spec);
+    let spec = "VER=1.0+git20230101\nUPSTREAM_VER=1.0\nREL=2\n";
+    assert_eq!(
+        replace_version(spec, "1.1", true, RelAction::Reset),
+        "VER=1.0+git20230101\nUPSTREAM_VER=1.1\nREL=0\n"
+    );
+    assert_eq!(
+        replace_version("VER=1.0\n", "1.1", false, RelAction::Reset),
+        "VER=1.1\n"
+    );
+}
+
+#[test]
+fn test_validate_version_format() {
+    let format = Regex::new(r"^\d+(\.\d+)*$").unwrap();
+    assert!(validate_version_format("1.2.3", &format).is_ok());
+    assert!(validate_version_format("20230718", &format).is_ok());
+    // scraped garbage
+    assert!(validate_version_format("1.2.3\">Download</a>", &format).is_err());
+    assert!(validate_version_format("latest", &format).is_err());
+}
+
+#[test]
+fn test_validate_upgrade() {
+    assert!(validate_upgrade("1.2.3", "1.2.4").is_ok());
+    assert!(validate_upgrade("1.2.3", "1.10").is_ok());
+    // equal
+    assert!(validate_upgrade("1.2.3", "1.2.3").is_err());
+    // lesser
+    assert!(validate_upgrade("1.2.3", "1.2.2").is_err());
+    // incomparable
+    assert!(validate_upgrade("1.2.3", "").is_err());
+}
+
+#[test]
+fn test_json_stream() {
+    let warnings = vec!["Compound version number '1.0+git'".to_string()];
+    let items = (0..3).map(|i| CheckResultOutput {
+        name: "ciel",
+        before: "1.0",
+        after: if i == 0 { "1.1" } else { "1.2" },
+        raw_upstream: "v1.2",
+        candidates_considered: 12,
+        path: "app-admin/ciel",
+        warnings: &warnings,
+        checked_at: "2024-01-01T00:00:00Z",
+        duration_ms: None,
+    });
+    let mut buf = Vec::new();
+    serde_json::to_writer(&mut buf, &JsonStream::new(items)).unwrap();
+    let parsed: Vec<serde_json::Value> = serde_json::from_reader(buf.as_slice()).unwrap();
+    assert_eq!(parsed.len(), 3);
+    assert_eq!(parsed[0]["after"], "1.1");
+    assert_eq!(parsed[2]["warnings"][0], warnings[0]);
+    // only present with --timings
+    assert!(parsed[0].get("duration_ms").is_none());
+    let output = JsonOutputV2 {
+        generated_at: "2024-01-01T00:00:00Z",
+        results: JsonStream::new(std::iter::empty::<CheckResultOutput>()),
+    };
+    let parsed: serde_json::Value =
+        serde_json::from_str(&serde_json::to_string(&output).unwrap()).unwrap();
+    assert_eq!(parsed["results"], serde_json::json!([]));
+    // the items are consumed by the first serialization
+    assert!(serde_json::to_string(&output).is_err());
+}
+
+#[test]
+fn test_matches_scheme() {
+    let spec = |pairs: &[(&str, &str)]| {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>()
+    };
+    let specs = [
+        spec(&[("VER", "1.2.3")]),
+        spec(&[("VER", "5.3-56")]),
+        spec(&[("VER", "2023-07-18")]),
+        spec(&[("VER", "1.0"), ("UPSTREAM_VER", "6.4-20230718")]),
+        spec(&[]),
+    ];
+    let selected = specs
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| matches_scheme(s, VersioningType::Revision))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert_eq!(selected, vec![1, 3]);
+    assert!(matches_scheme(&specs[2], "dashes".parse().unwrap()));
+    assert!("semantic".parse::<VersioningType>().is_err());
+}
+
+#[test]
+fn test_check_with_fallback() {
+    let mut s = HashMap::new();
+    s.insert(
+        "CHKUPDATE".to_string(),
+        "html::url=https://example.com/a".to_string(),
+    );
+    s.insert(
+        "CHKUPDATE__0".to_string(),
+        "git::url=https://example.com/b".to_string(),
+    );
+    s.insert(
+        "CHKUPDATE__1".to_string(),
+        "git::url=https://example.com/c".to_string(),
+    );
+    // not consecutive, so ignored
+    s.insert(
+        "CHKUPDATE__3".to_string(),
+        "git::url=https://example.com/d".to_string(),
+    );
+    let configs = get_check_configs(&s, "spec").unwrap();
+    let names = configs
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["CHKUPDATE", "CHKUPDATE__0", "CHKUPDATE__1"]);
+
+    // failing, then succeeding
+    let check = |config: &HashMap<String, String>| match config["type"].as_str() {
+        "git" => Ok(config["url"].clone()),
+        _ => Err(anyhow!("connection refused")),
+    };
+    let (index, version) = check_with_fallback(&configs, check).unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(version, "https://example.com/b");
+    // the primary checker succeeds
+    let (index, _) = check_with_fallback(&configs, |_| Ok("1.0".to_string())).unwrap();
+    assert_eq!(index, 0);
+    // all failed
+    let err =
+        check_with_fallback::<String, _>(&configs, |_| Err(anyhow!("timed out"))).unwrap_err();
+    assert!(err.to_string().starts_with("All 3 checkers failed"));
+
+    // variables of the spec are expanded
+    s.insert("PKGNAME".to_string(), "fractal".to_string());
+    s.insert(
+        "CHKUPDATE__0".to_string(),
+        "gitlab::repo=GNOME/${PKGNAME};instance=https://gitlab.gnome.org".to_string(),
+    );
+    let configs = get_check_configs(&s, "spec").unwrap();
+    assert_eq!(configs[1].1["repo"], "GNOME/fractal");
+    // except in the capture group template, even if the spec defines a variable of that name
+    s.insert("major".to_string(), "42".to_string());
+    s.insert(
+        "CHKUPDATE__0".to_string(),
+        r"git::url=https://example.com/b;pattern=^v(?<major>\d+)_(?<minor>\d+)$;replace=${major}.${minor}"
+            .to_string(),
+    );
+    let configs = get_check_configs(&s, "spec").unwrap();
+    assert_eq!(configs[1].1["replace"], "${major}.${minor}");
+
+    // fallback checkers only
+    s.remove("CHKUPDATE");
+    assert_eq!(get_check_configs(&s, "spec").unwrap().len(), 2);
+    assert!(get_check_configs(&HashMap::new(), "spec").is_err());
+}
+
+#[test]
+fn test_output_schema() {
+    let schema = output_schema();
+    let properties = |def: &str| {
+        let mut keys = schema["$defs"][def]["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    };
+    let keys = |value: serde_json::Value| {
+        let mut keys = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    };
+    let item = CheckResultOutput {
+        name: "ciel",
+        before: "1.0",
+        after: "1.1",
+        raw_upstream: "v1.1",
+        candidates_considered: 1,
+        path: "app-admin/ciel",
+        warnings: &[],
+        checked_at: "2024-01-01T00:00:00Z",
+        duration_ms: Some(1200),
+    };
+    let item = serde_json::to_value(&item).unwrap();
+    assert_eq!(keys(item.clone()), properties("result"));
+    let output = JsonOutputV2 {
+        generated_at: "2024-01-01T00:00:00Z",
+        results: [item],
+    };
+    assert_eq!(
+        keys(serde_json::to_value(&output).unwrap()),
+        properties("output_v2")
+    );
+}
+
+#[test]
+fn test_duplicate_names() {
+    let dirs = [
+        "app-utils/foo",
+        "lang-python/foo",
+        "app-utils/bar",
+        "core-libs/baz",
+        "extra-libs/baz",
+        "runtime-libs/baz",
+    ]
+    .map(PathBuf::from);
+    let names = duplicate_names(dirs);
+    assert_eq!(names.keys().collect::<Vec<_>>(), ["baz", "foo"]);
+    assert_eq!(
+        names["foo"],
+        [
+            PathBuf::from("app-utils/foo"),
+            PathBuf::from("lang-python/foo")
+        ]
+    );
+    assert_eq!(names["baz"].len(), 3);
+}
+
+#[test]
+fn test_write_spec() {
+    let dir = std::env::temp_dir().join(format!("aosc-findupdate-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec = dir.join("spec");
+    std::fs::write(&spec, "VER=1.0\nREL=1\n").unwrap();
+    write_spec(&spec, "VER=1.1\n", true).unwrap();
+    assert_eq!(std::fs::read_to_string(&spec).unwrap(), "VER=1.1\n");
+    assert_eq!(
+        std::fs::read_to_string(dir.join("spec.bak")).unwrap(),
+        "VER=1.0\nREL=1\n"
+    );
+    assert!(!dir.join(".spec.tmp").exists());
+    // simulate a failure by making the temporary file impossible to create
+    std::fs::create_dir(dir.join(".spec.tmp")).unwrap();
+    assert!(write_spec(&spec, "VER=1.2\n", false).is_err());
+    assert_eq!(std::fs::read_to_string(&spec).unwrap(), "VER=1.1\n");
+    // failing to back up the spec removes the temporary file
+    std::fs::remove_dir(dir.join(".spec.tmp")).unwrap();
+    std::fs::remove_file(dir.join("spec.bak")).unwrap();
+    std::fs::create_dir(dir.join("spec.bak")).unwrap();
+    assert!(write_spec(&spec, "VER=1.2\n", true).is_err());
+    assert_eq!(std::fs::read_to_string(&spec).unwrap(), "VER=1.1\n");
+    assert!(!dir.join(".spec.tmp").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_reparse_or_revert() {
+    let dir = std::env::temp_dir().join(format!(
+        "aosc-findupdate-test-revert-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec = dir.join("spec");
+    let original = "VER=1.0\nSRCS=\"tbl::https://example.com/foo-$VER.tar.gz\"\n";
+    std::fs::write(&spec, original).unwrap();
+    let (_, modified) = update_version("1.1", &spec, false, RelAction::Remove, false).unwrap();
+    assert!(
+        reparse_or_revert(&spec, original, &modified, &mut HashMap::new())
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(std::fs::read_to_string(&spec).unwrap(), modified);
+    // a version scraped with a line break in it breaks the spec
+    std::fs::write(&spec, original).unwrap();
+    let (_, modified) = update_version("1.1\n)", &spec, false, RelAction::Remove, false).unwrap();
+    assert!(
+        !reparse_or_revert(&spec, original, &modified, &mut HashMap::new())
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(std::fs::read_to_string(&spec).unwrap(), original);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_downgrade_warning() {
+    assert_eq!(downgrade_warning("1.0", "1.1", "1.1", false), None);
+    assert_eq!(
+        downgrade_warning("1.1", "1.0", "1.0", false).unwrap(),
+        "Possible downgrade from the current version (1.1 -> 1.0)"
+    );
+    // upstream re-tagged an older commit
+    assert_eq!(
+        downgrade_warning("2.4.1", "2.4.0", "2.4.0", true).unwrap(),
+        "Upstream version went backwards (2.4.1 -> 2.4.0)"
+    );
+    assert_eq!(downgrade_warning("2.4.1", "2.5.0", "2.5.0", true), None);
+}
+
+#[test]
+fn test_version_jump_warning() {
+    assert_eq!(
+        version_jump_warning("2.3.1", "5.0.0").unwrap(),
+        "Large version jump, verify manually (2.3.1 -> 5.0.0)"
+    );
+    assert_eq!(major_version("v12.0"), Some(12));
+    assert!(version_jump_warning("v1.9", "v12.0").is_some());
+    assert_eq!(version_jump_warning("2.3.1", "3.0.0"), None);
+    assert_eq!(version_jump_warning("2.3.1", "2.4.0"), None);
+    assert_eq!(version_jump_warning("5.0", "2.0"), None);
+    assert_eq!(version_jump_warning("20230101", "20240101"), None);
+    assert_eq!(version_jump_warning("git", "5.0"), None);
+}
+
+#[test]
+fn test_output_format_row() {
+    let fields = [
+        "foo",
+        "1.0",
+        "1.1",
+        "Compound version number '1.0+git1'; Hardcoded URLs detected.",
+    ];
+    assert_eq!(
+        OutputFormat::Csv.row(&fields),
+        "foo,1.0,1.1,\"Compound version number '1.0+git1'; Hardcoded URLs detected.\""
+    );
+    assert_eq!(
+        OutputFormat::Csv.row(&["foo", "1,0", "say \"hi\"", ""]),
+        "foo,\"1,0\",\"say \"\"hi\"\"\","
+    );
+    assert_eq!(
+        OutputFormat::Tsv.row(&fields),
+        "foo\t1.0\t1.1\tCompound version number '1.0+git1'; Hardcoded URLs detected."
+    );
+    assert_eq!(OutputFormat::Tsv.row(&["a\tb", "c\nd"]), "a b\tc d");
+}
+
+#[test]
+fn test_collect_spec_modified_since() {
+    let dir = std::env::temp_dir().join(format!("findupdate-collect-{}", std::process::id()));
+    for name in ["app-utils/old", "app-utils/new"] {
+        std::fs::create_dir_all(dir.join(name)).unwrap();
+        std::fs::write(dir.join(name).join("spec"), "VER=1.0\n").unwrap();
+    }
+    let week_ago = SystemTime::now() - Duration::from_secs(7 * 24 * 60 * 60);
+    File::options()
+        .write(true)
+        .open(dir.join("app-utils/old/spec"))
+        .unwrap()
+        .set_modified(week_ago - Duration::from_secs(60))
+        .unwrap();
+    let mut all = collect_spec(&dir, None).unwrap();
+    all.sort();
+    let recent = collect_spec(&dir, Some(week_ago)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(recent.len(), 1);
+    assert_eq!(normalize_name(&recent[0]), "new");
+}
+
+#[test]
+fn test_multiple_trees() {
+    let dir = std::env::temp_dir().join(format!("findupdate-trees-{}", std::process::id()));
+    let (main, overlay) = (dir.join("main"), dir.join("overlay"));
+    for (tree, name) in [
+        (&main, "app-utils/foo"),
+        (&main, "app-utils/bar"),
+        (&overlay, "extra-utils/foo"),
+    ] {
+        std::fs::create_dir_all(tree.join("groups")).unwrap();
+        std::fs::create_dir_all(tree.join(name)).unwrap();
+        std::fs::write(tree.join(name).join("spec"), "VER=1.0\n").unwrap();
+    }
+    let trees = [
+        main.canonicalize().unwrap(),
+        overlay.canonicalize().unwrap(),
+    ];
+    let mut paths = trees
+        .iter()
+        .flat_map(|tree| collect_spec(tree, None).unwrap())
+        .map(|spec| find_path(&spec, false).unwrap())
+        .collect::<Vec<_>>();
+    paths.sort();
+    let listed = specs_in_trees(
+        &["app-utils/foo".to_string(), "app-utils/baz".to_string()],
+        &trees,
+    );
+    let listed_overlay = specs_in_trees(&["extra-utils/foo".to_string()], &trees);
+    std::fs::remove_dir_all(&dir).unwrap();
+    // each spec is relative to its own tree
+    assert_eq!(paths, ["app-utils/bar", "app-utils/foo", "extra-utils/foo"]);
+    assert_eq!(
+        listed,
+        [
+            trees[0].join("app-utils/foo/spec"),
+            trees[0].join("app-utils/baz/spec")
+        ]
+    );
+    assert_eq!(listed_overlay, [trees[1].join("extra-utils/foo/spec")]);
+}
+
+#[test]
+fn test_same_package_in_trees() {
+    let dir = std::env::temp_dir().join(format!("findupdate-same-{}", std::process::id()));
+    let trees = [dir.join("main"), dir.join("overlay")].map(|tree| {
+        std::fs::create_dir_all(tree.join("groups")).unwrap();
+        std::fs::create_dir_all(tree.join("app-utils/foo")).unwrap();
+        std::fs::write(tree.join("app-utils/foo/spec"), "VER=1.0\n").unwrap();
+        tree.canonicalize().unwrap()
+    });
+    let specs = trees
+        .iter()
+        .map(|tree| tree.join("app-utils/foo/spec"))
+        .collect::<Vec<_>>();
+    let mut results = specs
+        .iter()
+        .map(|_| Ok(checker_result("foo", "1.0", "1.1")))
+        .collect::<Vec<_>>();
+    let with_tree = trees_of(&trees).len() > 1;
+    resolve_paths(&mut results, &specs, with_tree);
+    let diffs = format_diffs(&results);
+    std::fs::remove_dir_all(&dir).unwrap();
+    // each package keeps its own path, prefixed with its tree
+    let paths = results
+        .iter()
+        .map(|ret| ret.as_ref().unwrap().path.clone().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        trees.map(|tree| tree.join("app-utils/foo").display().to_string())
+    );
+    for path in paths {
+        assert!(diffs.contains(&format!("+++ b/{}/spec\n", path)));
+    }
+}
+
+#[cfg(test)]
+fn checker_result(name: &str, before: &str, after: &str) -> CheckerResult {
+    CheckerResult {
+        name: name.to_string(),
+        checker_type: "github".to_string(),
+        before: before.to_string(),
+        after: after.to_string(),
+        raw_upstream: after.to_string(),
+        candidates_considered: 1,
+        warnings: Vec::new(),
+        change: (before != after).then(|| SpecChange {
+            before: format!("VER={}\nREL=1\n", before),
+            after: format!("VER={}\n", after),
+        }),
+        path: None,
+    }
+}
+
+#[test]
+fn test_run_outcome() {
+    let result = |before, after| Ok(checker_result("foo", before, after));
+    assert_eq!(RunOutcome::from_results(&[]), RunOutcome::UpToDate);
+    assert_eq!(
+        RunOutcome::from_results(&[result("1.0", "1.0")]),
+        RunOutcome::UpToDate
+    );
+    assert_eq!(
+        RunOutcome::from_results(&[result("1.0", "1.0"), result("1.0", "1.1")]),
+        RunOutcome::UpdatesFound
+    );
+    assert_eq!(
+        RunOutcome::from_results(&[result("1.0", "1.1"), Err(anyhow!("bar: 404"))]),
+        RunOutcome::Errors
+    );
+    assert_eq!(RunOutcome::UpToDate.exit_code(), 0);
+    assert_ne!(
+        RunOutcome::UpdatesFound.exit_code(),
+        RunOutcome::Errors.exit_code()
+    );
+}
+
+#[test]
+fn test_use_color() {
+    assert!(use_color("auto", true, None, Some("xterm-256color")));
+    assert!(!use_color("auto", false, None, None));
+    assert!(!use_color("auto", true, Some("1"), None));
+    // an empty NO_COLOR is ignored
+    assert!(use_color("auto", true, Some(""), None));
+    assert!(!use_color("auto", true, None, Some("dumb")));
+    assert!(use_color("always", false, Some("1"), None));
+    assert!(!use_color("never", true, None, None));
+    COLOR.store(false, Ordering::Relaxed);
+    assert_eq!(
+        format!("{:<6}|", paint("foo", Style::new().red())),
+        "foo   |"
+    );
+}
+
+#[test]
+fn test_format_diffs() {
+    let result = |name, before, after| Ok(checker_result(name, before, after));
+    let mut results = [
+        result("zstd", "1.5.5", "1.5.6"),
+        result("curl", "8.7.1", "8.7.1"),
+        Err(anyhow!("foo: 404")),
+        result("bash", "5.2.21", "5.2.26"),
+    ];
+    results[3].as_mut().unwrap().path = Some("app-shells/bash".to_string());
+    let diffs = format_diffs(&results);
+    assert_eq!(
+        diffs,
+        "diff --git a/app-shells/bash/spec b/app-shells/bash/spec\n\
+         --- a/app-shells/bash/spec\n\
+         +++ b/app-shells/bash/spec\n\
+         @@ -1,2 +1 @@\n\
+         -VER=5.2.21\n\
+         -REL=1\n\
+         +VER=5.2.26\n\
+         diff --git a/zstd/spec b/zstd/spec\n\
+         --- a/zstd/spec\n\
+         +++ b/zstd/spec\n\
+         @@ -1,2 +1 @@\n\
+         -VER=1.5.5\n\
+         -REL=1\n\
+         +VER=1.5.6\n"
+    );
+}
+
+#[test]
+fn test_run_summary() {
+    let result = |before, after, warnings: &[&str]| {
+        let mut result = checker_result("foo", before, after);
+        result.warnings = warnings.iter().map(|w| w.to_string()).collect();
+        Ok(result)
+    };
+    let results = [
+        result(
+            "1.0",
+            "1.1",
+            &["Large version jump, verify manually (1.0 -> 1.1)"],
+        ),
+        result("2.0", "2.0", &[]),
+        Err(anyhow!("bar: 404")),
+        result("3.0", "3.1", &["a", "b"]),
+        Err(anyhow!("baz: timed out")),
+    ];
+    let summary = RunSummary::from_results(&results);
+    assert_eq!(
+        summary,
+        RunSummary {
+            checked: 5,
+            updated: 2,
+            errors: 2,
+            warnings: 3,
+        }
+    );
+    assert_eq!(
+        summary.to_string(),
+        "5 checked, 2 updated, 2 errors, 3 warnings"
+    );
+    assert_eq!(
+        RunSummary::from_results(&[]).to_string(),
+        "0 checked, 0 updated, 0 errors, 0 warnings"
+    );
+}
+
+#[test]
+fn test_format_slowest() {
+    let timings = [("foo", 120), ("bar", 4500), ("baz", 80), ("qux", 4500)]
+        .map(|(name, ms)| (name.to_string(), Duration::from_millis(ms)));
+    assert_eq!(
+        format_slowest(&timings, 3),
+        "Slowest 3 packages:\n    4500 ms  bar\n    4500 ms  qux\n     120 ms  foo\n"
+    );
+    assert_eq!(format_slowest(&timings[..1], 10).lines().count(), 2);
+    assert_eq!(format_slowest(&[], 10), "Slowest 0 packages:\n");
+}
 
-    path.ok_or_else(|| anyhow!("Failed to get package path: {}", name))
+#[test]
+fn test_fail_fast() {
+    let fail_fast = FailFast::new(true);
+    assert!(!fail_fast.skip());
+    fail_fast.record(0, &Ok(()));
+    assert!(!fail_fast.skip());
+    fail_fast.record(3, &Err::<(), _>(anyhow!("foo: 404")));
+    fail_fast.record(1, &Err::<(), _>(anyhow!("bar: 404")));
+    // the first failure is kept, and the following checks are skipped
+    assert_eq!(fail_fast.first_error.get(), Some(&3));
+    assert!(fail_fast.skip());
+    assert!(fail_fast.skip());
+    assert_eq!(fail_fast.skipped.load(Ordering::SeqCst), 2);
+    // by default, the run continues on errors
+    let fail_fast = FailFast::new(false);
+    fail_fast.record(0, &Err::<(), _>(anyhow!("foo: 404")));
+    assert!(!fail_fast.skip());
 }
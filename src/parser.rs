@@ -1,7 +1,8 @@
 use anyhow::{anyhow, Result};
-use log::{info, warn};
+use log::{debug, info, warn};
+use regex::{Captures, Regex};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader, Read},
     path::Path,
@@ -41,13 +42,69 @@ fn config_line<'a>(input: &mut &'a str) -> PResult<(&'a str, Vec<(&'a str, &'a s
     separated_pair(take_type, CONFIG_SEPARATOR, kv_pairs).parse_next(input)
 }
 
-pub(crate) fn parse_spec<P: AsRef<Path>>(spec: P) -> Result<Context> {
-    let mut f = File::open(spec.as_ref())?;
-    let mut contents = String::new();
-    f.read_to_string(&mut contents)?;
+/// Variables needed to check for updates, which the fast path reads without evaluating the spec
+const FAST_PATH_VARIABLES: &[&str] = &["VER", "UPSTREAM_VER", "CHKUPDATE", "CHKUPDATE_KEEP_REL"];
+
+fn is_fast_path_variable(name: &str) -> bool {
+    FAST_PATH_VARIABLES.contains(&name)
+        || name
+            .strip_prefix("CHKUPDATE__")
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|c| c.is_ascii_digit()))
+}
+
+/// Returns the literal value of an assignment, or `None` if it needs evaluation
+fn literal_value(value: &str) -> Option<&str> {
+    if let Some(value) = value.strip_prefix('\'') {
+        // single quotes don't expand anything
+        return value.strip_suffix('\'').filter(|v| !v.contains('\''));
+    }
+    let (value, quoted) = match value.strip_prefix('"') {
+        Some(value) => (value.strip_suffix('"')?, true),
+        None => (value, false),
+    };
+    if value.contains(['$', '`', '\\', '"', '\''])
+        || (!quoted && value.contains(char::is_whitespace))
+    {
+        return None;
+    }
+
+    Some(value)
+}
+
+/// Extracts the variables needed for checking updates line by line, returns `None` if any of them
+/// can't be resolved without evaluating the spec
+fn scan_spec(contents: &str) -> Option<Context> {
+    let mut context = HashMap::new();
+    for line in contents.lines() {
+        let Some((name, value)) = line.trim_start().split_once('=') else {
+            continue;
+        };
+        if !is_fast_path_variable(name.strip_suffix('+').unwrap_or(name)) {
+            continue;
+        }
+        // appending to or conditionally assigning the variable
+        if name.ends_with('+') || line.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let value = literal_value(value.trim_end())?;
+        // references expanded by `expand_variables` may point to any variable of the spec
+        if name.starts_with("CHKUPDATE") && value.contains("${") {
+            return None;
+        }
+        context.insert(name.to_string(), value.to_string());
+    }
+    if !context.contains_key("VER") && !context.contains_key("UPSTREAM_VER") {
+        return None;
+    }
+
+    Some(context)
+}
+
+/// Evaluates the whole spec
+pub(crate) fn evaluate_spec(contents: &str) -> Result<Context> {
     let mut context = HashMap::new();
 
-    abbs_meta_apml::parse(&contents, &mut context).map_err(|e| {
+    abbs_meta_apml::parse(contents, &mut context).map_err(|e| {
         let mut s = String::new();
         for (i, c) in e.iter().enumerate() {
             if i != e.len() - 1 {
@@ -63,6 +120,22 @@ pub(crate) fn parse_spec<P: AsRef<Path>>(spec: P) -> Result<Context> {
     Ok(context)
 }
 
+/// Reads the variables of the spec. Unless `full_parse` is set, only the variables needed for
+/// checking updates are extracted, and the spec is evaluated only when they can't be resolved
+pub(crate) fn parse_spec<P: AsRef<Path>>(spec: P, full_parse: bool) -> Result<Context> {
+    let mut f = File::open(spec.as_ref())?;
+    let mut contents = String::new();
+    f.read_to_string(&mut contents)?;
+    if !full_parse {
+        if let Some(context) = scan_spec(&contents) {
+            return Ok(context);
+        }
+        debug!("{}: falling back to full parse", spec.as_ref().display());
+    }
+
+    evaluate_spec(&contents)
+}
+
 pub(crate) fn parse_check_update(content: &mut &str) -> Result<Context> {
     let parsed = config_line(content).map_err(|err| anyhow!("Invalid config line: {}", err))?;
     let mut context = HashMap::new();
@@ -76,6 +149,53 @@ pub(crate) fn parse_check_update(content: &mut &str) -> Result<Context> {
     Ok(context)
 }
 
+/// Expands the `${NAME}` references in a value with the variables of the spec, e.g. to use
+/// `repo=foo/${PKGNAME}`. `$${NAME}` is kept as a literal `${NAME}`. Unknown references are kept
+/// as-is, and their names are returned along with the expanded value
+pub(crate) fn expand_variables(value: &str, context: &Context) -> (String, Vec<String>) {
+    let reference = Regex::new(r"\$(\$)?\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap();
+    let mut missing = Vec::new();
+    let expanded = reference.replace_all(value, |c: &Captures| {
+        let name = &c[2];
+        if c.get(1).is_some() {
+            return format!("${{{}}}", name);
+        }
+        match context.get(name) {
+            Some(v) => v.clone(),
+            None => {
+                missing.push(name.to_string());
+                c[0].to_string()
+            }
+        }
+    });
+
+    (expanded.into_owned(), missing)
+}
+
+/// Returns the trimmed entry on a line of a package list, or `None` for comments
+fn list_entry(line: &str) -> Option<&str> {
+    // skip comment
+    if line.starts_with('#') {
+        return None;
+    }
+    // trim whitespace
+    Some(line.trim())
+}
+
+/// Read a list of package names to skip, one per line, with `#` comments and blank lines ignored
+pub(crate) fn read_skip_list<P: AsRef<Path>>(filename: P) -> Result<HashSet<String>> {
+    let reader = BufReader::new(File::open(filename)?);
+    let mut results = HashSet::new();
+    for line in reader.lines() {
+        let line = line?;
+        if let Some(name) = list_entry(&line).filter(|x| !x.is_empty()) {
+            results.insert(name.to_owned());
+        }
+    }
+
+    Ok(results)
+}
+
 // copied from ciel
 
 fn read_package_list<P: AsRef<Path>>(filename: P, depth: usize) -> Result<Vec<String>> {
@@ -89,12 +209,9 @@ fn read_package_list<P: AsRef<Path>>(filename: P, depth: usize) -> Result<Vec<St
     let mut results = Vec::new();
     for line in reader.lines() {
         let line = line?;
-        // skip comment
-        if line.starts_with('#') {
+        let Some(trimmed) = list_entry(&line) else {
             continue;
-        }
-        // trim whitespace
-        let trimmed = line.trim();
+        };
         // process nested groups
         if trimmed.starts_with("groups/") {
             let path = Path::new(".").join(trimmed);
@@ -169,3 +286,76 @@ fn test_kv_pairs() {
     assert_eq!(res, Ok(vec![("a", "b"), ("b", "d")]));
     assert_eq!(test, &mut "");
 }
+
+#[test]
+fn test_scan_spec_simple() {
+    let spec = "VER=1.2.3\nREL=1\nSRCS=\"tbl::https://example.com/foo-1.2.3.tar.gz\"\nCHKSUMS=\"sha256::0000\"\nCHKUPDATE=\"anitya::id=1234\"\n";
+    let fast = scan_spec(spec).unwrap();
+    let full = evaluate_spec(spec).unwrap();
+    assert_eq!(fast.len(), 2);
+    for (k, v) in fast.iter() {
+        assert_eq!(full.get(k), Some(v));
+    }
+    let spec = "UPSTREAM_VER=2.0-rc1\nVER=2.0~rc1\nCHKUPDATE=\"github::repo=foo/bar\"\nCHKUPDATE__0=\"anitya::id=1\"\nCHKUPDATE_KEEP_REL=1\n";
+    let fast = scan_spec(spec).unwrap();
+    let full = evaluate_spec(spec).unwrap();
+    assert_eq!(fast.len(), 5);
+    for (k, v) in fast.iter() {
+        assert_eq!(full.get(k), Some(v));
+    }
+}
+
+#[test]
+fn test_scan_spec_complex() {
+    assert_eq!(scan_spec("VER='1.2.3'\n").unwrap()["VER"], "1.2.3");
+    // expansions in the other variables don't matter
+    let spec = "VER=1.2.3\nSRCS=\"tbl::https://example.com/foo-$VER.tar.gz\"\nCHKUPDATE=\"anitya::id=1234\"\n";
+    let fast = scan_spec(spec).unwrap();
+    let full = evaluate_spec(spec).unwrap();
+    assert_eq!(fast["VER"], full["VER"]);
+    assert_eq!(fast["CHKUPDATE"], full["CHKUPDATE"]);
+    // these need the full parse
+    assert!(scan_spec("__VER=1.2.3\nVER=\"${__VER}\"\n").is_none());
+    assert!(scan_spec("VER=1.2.3\nCHKUPDATE=\"anitya::id=$ID\"\n").is_none());
+    assert!(scan_spec("VER=1.2\nVER+=.3\n").is_none());
+    assert!(scan_spec("VER=1.2.3\nCHKUPDATE='github::repo=foo/${_REPO}'\n").is_none());
+    assert!(scan_spec("VER=\"1.2.3\n\"\n").is_none());
+    assert!(scan_spec("VER=$(echo 1.2.3)\n").is_none());
+    assert!(scan_spec("SRCS=\"tbl::https://example.com/foo.tar.gz\"\n").is_none());
+}
+
+#[test]
+fn test_read_skip_list() {
+    let path = std::env::temp_dir().join(format!("findupdate-skip-{}", std::process::id()));
+    std::fs::write(&path, "# known broken\nfoo\n\n  bar  \n#baz\n").unwrap();
+    let list = read_skip_list(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(list, HashSet::from(["foo".to_string(), "bar".to_string()]));
+}
+
+#[test]
+fn test_expand_variables() {
+    let mut context = HashMap::new();
+    context.insert("PKGNAME".to_string(), "fractal".to_string());
+    context.insert("VER".to_string(), "5.0".to_string());
+    assert_eq!(
+        expand_variables("GNOME/${PKGNAME}", &context),
+        ("GNOME/fractal".to_string(), vec![])
+    );
+    // regular expressions are left alone
+    assert_eq!(
+        expand_variables(r"^${PKGNAME}-(\d+\.\d{2})$", &context),
+        (r"^fractal-(\d+\.\d{2})$".to_string(), vec![])
+    );
+    assert_eq!(
+        expand_variables("${UPSTREAM}/${PKGNAME}", &context),
+        (
+            "${UPSTREAM}/fractal".to_string(),
+            vec!["UPSTREAM".to_string()]
+        )
+    );
+    assert_eq!(
+        expand_variables("$${VER}-${VER}", &context),
+        ("${VER}-5.0".to_string(), vec![])
+    );
+}
@@ -0,0 +1,74 @@
+//! Minimal template engine for exporting the results in custom formats.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// Placeholders available in the templates
+pub(crate) const PLACEHOLDERS: &[&str] = &["name", "before", "after", "path", "type"];
+
+/// Substitutes the `{key}` placeholders in the template, `{{` and `}}` are literal braces
+pub(crate) fn render(template: &str, values: &HashMap<&str, &str>) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        output.push_str(&rest[..i]);
+        let brace = &rest[i..i + 1];
+        let after = &rest[i + 1..];
+        if let Some(after) = after.strip_prefix(brace) {
+            output.push_str(brace);
+            rest = after;
+            continue;
+        }
+        if brace == "}" {
+            return Err(anyhow!(
+                "Unmatched `}}` in template, use `}}}}` for a literal brace"
+            ));
+        }
+        let end = after
+            .find('}')
+            .ok_or_else(|| anyhow!("Unterminated placeholder in template"))?;
+        let key = &after[..end];
+        let value = values
+            .get(key)
+            .ok_or_else(|| anyhow!("Unknown placeholder in template: {{{}}}", key))?;
+        output.push_str(value);
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+/// Makes sure the template only uses the known placeholders
+pub(crate) fn validate(template: &str) -> Result<()> {
+    let values = PLACEHOLDERS.iter().map(|k| (*k, "")).collect();
+    render(template, &values)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_render() {
+    let values = HashMap::from([("name", "ciel"), ("before", "3.0.0"), ("after", "3.1.0")]);
+    assert_eq!(
+        render("{name}: {before} -> {after}\n", &values).unwrap(),
+        "ciel: 3.0.0 -> 3.1.0\n"
+    );
+    assert_eq!(
+        render("{{\"{name}\": \"{after}\"}}", &values).unwrap(),
+        "{\"ciel\": \"3.1.0\"}"
+    );
+    assert_eq!(
+        render("no placeholders", &values).unwrap(),
+        "no placeholders"
+    );
+    assert!(render("{version}", &values).is_err());
+    assert!(render("{name", &values).is_err());
+    assert!(render("name}", &values).is_err());
+}
+
+#[test]
+fn test_validate() {
+    assert!(validate("{name} {before} {after} {path} {type}").is_ok());
+    assert!(validate("{checked_at}").is_err());
+}